@@ -0,0 +1,182 @@
+use super::*;
+
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+/// Describes how an aliquot sequence stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AliquotEnd {
+    /// The sequence reached 0, which has no successor.
+    Terminated,
+    /// The sequence returned to a value it had already produced.  Holds that value.
+    Cycle(u64),
+    /// The next term does not fit in a u64.
+    Overflow,
+}
+
+/// Iterates over an aliquot sequence `n, s(n), s(s(n)), ...`, where `s(n) = σ(n) - n` is the sum
+/// of the proper divisors of `n`.
+///
+/// The iterator stops after yielding 0, before yielding any value for the second time, or when
+/// the next value would overflow a u64.  Once it has stopped, `end()` reports which of these
+/// happened.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::{ AliquotSeq, AliquotEnd };
+///
+/// fn main() {
+///     let mut seq = AliquotSeq::new(95);
+///     assert_eq!(seq.by_ref().collect::<Vec<u64>>(), vec![95, 25, 6]);
+///     assert_eq!(seq.end(), Some(AliquotEnd::Cycle(6)));
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct AliquotSeq {
+    next: Option<u64>,
+    seen: HashSet<u64>,
+    end: Option<AliquotEnd>,
+}
+
+impl AliquotSeq {
+    /// Returns the aliquot sequence starting at `n`.
+    pub fn new(n: u64) -> Self {
+        AliquotSeq { next: Some(n), seen: HashSet::new(), end: None }
+    }
+    /// Reports how the sequence ended, or `None` if it hasn't ended yet.
+    pub fn end(&self) -> Option<AliquotEnd> {
+        self.end
+    }
+}
+
+/// The sum of the proper divisors of `n`, or `None` on overflow.  `n` must not be 0.
+///
+/// σ(n) itself can overflow a u64 while σ(n) - n still fits, so it is computed in u128.
+pub(crate) fn aliquot_sum(n: u64) -> Option<u64> {
+    let s = factor(n).divisor_sum_u128()? - n as u128;
+    u64::try_from(s).ok()
+}
+
+impl Iterator for AliquotSeq {
+    type Item = u64;
+    fn next(&mut self) -> Option<Self::Item> {
+        let cur = self.next.take()?;
+        self.seen.insert(cur);
+        if cur == 0 {
+            self.end = Some(AliquotEnd::Terminated);
+        } else {
+            match aliquot_sum(cur) {
+                None => self.end = Some(AliquotEnd::Overflow),
+                Some(s) if self.seen.contains(&s) => self.end = Some(AliquotEnd::Cycle(s)),
+                Some(s) => self.next = Some(s),
+            }
+        }
+        Some(cur)
+    }
+}
+
+/// Determines whether `a` and `b` form an amicable pair.
+///
+/// That is, `a != b`, the proper divisors of `a` sum to `b`, and those of `b` sum to `a`.
+pub fn is_amicable_pair(a: u64, b: u64) -> bool {
+    a != b && a > 0 && b > 0 && aliquot_sum(a) == Some(b) && aliquot_sum(b) == Some(a)
+}
+
+/// Returns the amicable partner of `n`, if `n` belongs to an amicable pair.
+///
+/// Perfect numbers are not considered to be their own partners.
+pub fn amicable_partner(n: u64) -> Option<u64> {
+    if n == 0 {
+        return None;
+    }
+    let m = aliquot_sum(n)?;
+    if is_amicable_pair(n, m) {
+        Some(m)
+    } else {
+        None
+    }
+}
+
+/// Finds the sociable cycle through `n`, if it has at most `max_len` members.
+///
+/// Follows the aliquot sequence from `n` for up to `max_len` steps.  If it returns to `n`, the
+/// members of the cycle are returned in sequence order starting with `n`.  Perfect numbers give
+/// cycles of length 1 and amicable pairs cycles of length 2.
+pub fn sociable_cycle(n: u64, max_len: usize) -> Option<Vec<u64>> {
+    let mut cycle = Vec::new();
+    let mut cur = n;
+    while cycle.len() < max_len && cur != 0 {
+        cycle.push(cur);
+        cur = aliquot_sum(cur)?;
+        if cur == n {
+            return Some(cycle);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(n: u64) -> (Vec<u64>, Option<AliquotEnd>) {
+        let mut seq = AliquotSeq::new(n);
+        let v = seq.by_ref().collect();
+        (v, seq.end())
+    }
+
+    #[test]
+    fn known_sequences() {
+        assert_eq!(run(0), (vec![0], Some(AliquotEnd::Terminated)));
+        assert_eq!(run(1), (vec![1, 0], Some(AliquotEnd::Terminated)));
+        assert_eq!(run(10), (vec![10, 8, 7, 1, 0], Some(AliquotEnd::Terminated)));
+        assert_eq!(run(6), (vec![6], Some(AliquotEnd::Cycle(6))));
+        assert_eq!(run(220), (vec![220, 284], Some(AliquotEnd::Cycle(220))));
+        assert_eq!(run(562), (vec![562, 284, 220], Some(AliquotEnd::Cycle(284))));
+        assert_eq!(run(12496), (vec![12496, 14288, 15472, 14536, 14264], Some(AliquotEnd::Cycle(12496))));
+        assert_eq!(run(3 << 62), (vec![3 << 62], Some(AliquotEnd::Overflow)));
+        // σ(2q) = 3(q + 1) overflows, but s(2q) = q + 3 does not.
+        let q = prev_prime(1 << 63).unwrap().get();
+        assert_eq!(aliquot_sum(2 * q), Some(q + 3));
+        assert_eq!(AliquotSeq::new(2 * q).nth(1), Some(q + 3));
+    }
+
+    #[test]
+    fn amicable_pairs() {
+        let pairs: Vec<(u64, u64)> = (1..20_000)
+            .filter_map(|n| amicable_partner(n).map(|m| (n, m)))
+            .filter(|(n, m)| n < m)
+            .collect();
+        assert_eq!(pairs, vec![(220, 284), (1184, 1210), (2620, 2924), (5020, 5564), (6232, 6368),
+                               (10744, 10856), (12285, 14595), (17296, 18416)]);
+        assert!(is_amicable_pair(284, 220));
+        assert!(!is_amicable_pair(6, 6));
+        assert_eq!(amicable_partner(28), None);
+        assert_eq!(amicable_partner(0), None);
+    }
+
+    #[test]
+    fn sociable_cycles() {
+        assert_eq!(sociable_cycle(28, 1), Some(vec![28]));
+        assert_eq!(sociable_cycle(220, 1), None);
+        assert_eq!(sociable_cycle(220, 2), Some(vec![220, 284]));
+        assert_eq!(sociable_cycle(14288, 10), Some(vec![14288, 15472, 14536, 14264, 12496]));
+        assert_eq!(sociable_cycle(14316, 27), None);
+        assert_eq!(sociable_cycle(14316, 28).map(|c| c.len()), Some(28));
+        assert_eq!(sociable_cycle(10, 100), None);
+        assert_eq!(sociable_cycle(562, 100), None);
+    }
+
+    #[test]
+    fn small_sequences_end() {
+        // Everything below 276 ends quickly; 276 is the first open Lehmer five member.
+        for n in 1..276 {
+            let (v, end) = run(n);
+            assert!(end.is_some(), "aliquot sequence for {} did not end", n);
+            for w in v.windows(2) {
+                assert_eq!(aliquot_sum(w[0]), Some(w[1]));
+            }
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use super::*;
+
+use super::modular::{ inv_mod, mul_mod };
+use super::sieve::SegmentSieve;
+
+/// Calls `f` on every prime up to and including `n`, in ascending order.
+fn for_primes_upto<F: FnMut(Prime)>(n: u64, mut f: F) {
+    SegmentSieve::new(n).for_segments(2, n, |primes| {
+        for &p in primes {
+            // everything the sieve yields is prime
+            f(unsafe { Prime::new_unsafe(p) });
+        }
+        true
+    });
+}
+
+/// The exponent of the prime `p` in `n!`, by Legendre's formula `Σ floor(n / p^i)`.
+fn legendre(n: u64, p: u64) -> u64 {
+    let mut res = 0;
+    let mut q = n / p;
+    while q > 0 {
+        res += q;
+        q /= p;
+    }
+    res
+}
+
+/// Factors `n!` without calculating it.
+///
+/// Every prime `p <= n` appears with the exponent given by Legendre's formula,
+/// `floor(n/p) + floor(n/p^2) + ...`.  The primes are found with a segmented sieve, so the cost
+/// is roughly linear in `n`, and the factorization holds π(n) entries.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::factor_factorial;
+///
+/// fn main() {
+///     let pf = factor_factorial(10);
+///     let facs: Vec<(u64, u64)> = pf.iter().map(|(p, k)| (p.get(), k)).collect();
+///     assert_eq!(facs, vec![(2, 8), (3, 4), (5, 2), (7, 1)]);
+///     assert_eq!(pf.product(), 3_628_800);
+/// }
+/// ```
+pub fn factor_factorial(n: u64) -> PrimeFactorization {
+    let mut pf = PrimeFactorization::new();
+    for_primes_upto(n, |p| pf.add(p, legendre(n, p.get())));
+    pf
+}
+
+/// Calculates the exponent of `p` in the binomial coefficient C(n, k).
+///
+/// By Kummer's theorem this is the number of carries when adding `k` and `n - k` in base `p`.
+/// It is computed from Legendre's formula as `v_p(n!) - v_p(k!) - v_p((n-k)!)`.
+///
+/// # Panics
+///
+/// Panics if `k > n`, since C(n, k) is then 0.
+pub fn binomial_valuation(n: u64, k: u64, p: Prime) -> u64 {
+    assert!(k <= n, "binomial_valuation called with k = {} > n = {}", k, n);
+    let p = p.get();
+    legendre(n, p) - legendre(k, p) - legendre(n - k, p)
+}
+
+/// Factors the binomial coefficient C(n, k) without calculating it.
+///
+/// Each prime up to `n` gets the exponent from `binomial_valuation()`, and those that don't
+/// divide C(n, k) are left out.  Like `factor_factorial()`, the cost is roughly linear in `n`.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::factor_binomial;
+///
+/// fn main() {
+///     // C(10, 4) = 210 = 2 * 3 * 5 * 7
+///     assert_eq!(factor_binomial(10, 4).product(), 210);
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `k > n`, since C(n, k) is then 0.
+pub fn factor_binomial(n: u64, k: u64) -> PrimeFactorization {
+    assert!(k <= n, "factor_binomial called with k = {} > n = {}", k, n);
+    let mut pf = PrimeFactorization::new();
+    for_primes_upto(n, |p| pf.add(p, binomial_valuation(n, k, p)));
+    pf
+}
+
+/// C(n, k) mod p for `k <= n < p`, from the shorter of the two products for C(n, k).
+fn small_binomial_mod(n: u64, k: u64, p: u64) -> u64 {
+    let k = k.min(n - k);
+    let mut num = 1;
+    let mut den = 1;
+    for i in 0..k {
+        num = mul_mod(num, n - i, p);
+        den = mul_mod(den, i + 1, p);
+    }
+    // den is a product of numbers below p, so it is invertible
+    mul_mod(num, inv_mod(den, p).unwrap(), p)
+}
+
+/// Calculates the binomial coefficient C(n, k) modulo a prime `p`, using Lucas' theorem.
+///
+/// Writing `n` and `k` in base `p`, C(n, k) is congruent to the product of the binomial
+/// coefficients of their corresponding digits.  Each digit pair costs up to `min(k_i, n_i - k_i)`
+/// multiplications, so this is fast when `p` is small or the digits of `k` are near 0 or near
+/// those of `n`.
+///
+/// Returns 0 when `k > n`.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::{ binomial_mod_prime, Prime };
+///
+/// fn main() {
+///     let p = Prime::new(7).unwrap();
+///     // C(10, 3) = 120 = 17 * 7 + 1
+///     assert_eq!(binomial_mod_prime(10, 3, p), 1);
+/// }
+/// ```
+pub fn binomial_mod_prime(n: u64, k: u64, p: Prime) -> u64 {
+    let p = p.get();
+    let (mut n, mut k) = (n, k);
+    let mut res = 1 % p;
+    while k > 0 {
+        let (ni, ki) = (n % p, k % p);
+        if ki > ni {
+            return 0;
+        }
+        res = mul_mod(res, small_binomial_mod(ni, ki, p), p);
+        n /= p;
+        k /= p;
+    }
+    res
+}
+
+/// Calculates `n! mod p` for a prime `p`.
+///
+/// This is 0 when `n >= p`.  Otherwise whichever is shorter of `1 * 2 * ... * n` and
+/// `(n+1) * ... * (p-1)` is multiplied out, using Wilson's theorem `(p-1)! ≡ -1 (mod p)` in the
+/// second case, so the cost is `min(n, p - n)` multiplications.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::{ factorial_mod_prime, Prime };
+///
+/// fn main() {
+///     let p = Prime::new(1_000_000_007).unwrap();
+///     // (p-2)! ≡ 1 (mod p)
+///     assert_eq!(factorial_mod_prime(1_000_000_005, p), 1);
+/// }
+/// ```
+pub fn factorial_mod_prime(n: u64, p: Prime) -> u64 {
+    let p = p.get();
+    if n >= p {
+        return 0;
+    }
+    if n < p - 1 - n {
+        (2..=n).fold(1, |acc, i| mul_mod(acc, i, p))
+    } else {
+        let tail = (n + 1..p).fold(1, |acc, i| mul_mod(acc, i, p));
+        // n! * tail ≡ -1
+        p - inv_mod(tail, p).unwrap()
+    }
+}
+
+/// Calculates the Wilson quotient `((p-1)! + 1) / p` modulo `p`.
+///
+/// Wilson's theorem says that `p` divides `(p-1)! + 1`.  The primes for which the quotient is
+/// also divisible by `p` are the Wilson primes, of which only 5, 13 and 563 are known.
+///
+/// `(p-1)!` is computed modulo `p^2` one factor at a time, so this takes O(p) time and is
+/// practical up to around 10^9.
+///
+/// # Panics
+///
+/// Panics if `p` is 2^32 or more, where `p^2` no longer fits in a u64.
+pub fn wilson_quotient(p: Prime) -> u64 {
+    let p = p.get();
+    assert!(p < 1 << 32, "wilson_quotient called with p = {} >= 2^32", p);
+    let p2 = p * p;
+    let fact = (2..p).fold(1 % p2, |acc, i| mul_mod(acc, i, p2));
+    ((fact + 1) % p2) / p
+}
+
+/// Calculates the primorial `n#`, the product of all primes up to and including `n`.
+///
+/// # Panics
+///
+/// Panics if the result does not fit in a u64, which happens from `n = 53` on.  Use
+/// `checked_primorial()` or `checked_primorial_u128()` to avoid this.
+pub fn primorial(n: u64) -> u64 {
+    checked_primorial(n).expect("primorial overflowed u64")
+}
+
+/// Calculates the primorial `n#`, or `None` if it does not fit in a u64.
+pub fn checked_primorial(n: u64) -> Option<u64> {
+    let mut res: u64 = 1;
+    for p in CertIter::all().take_while(|p| p.get() <= n) {
+        res = res.checked_mul(p.get())?;
+    }
+    Some(res)
+}
+
+/// Calculates the primorial `n#` as a u128, or `None` if it does not fit, from `n = 103` on.
+pub fn checked_primorial_u128(n: u64) -> Option<u128> {
+    let mut res: u128 = 1;
+    for p in CertIter::all().take_while(|p| p.get() <= n) {
+        res = res.checked_mul(p.get() as u128)?;
+    }
+    Some(res)
+}
+
+/// Factors the primorial `n#`: every prime up to and including `n`, each to the first power.
+///
+/// The primes come straight from `CertIter`, so no factoring is done, and the result has π(n)
+/// entries.
+pub fn factor_primorial(n: u64) -> PrimeFactorization {
+    let mut pf = PrimeFactorization::new();
+    for p in CertIter::all().take_while(|p| p.get() <= n) {
+        pf.add(p, 1);
+    }
+    pf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_factorials() {
+        let mut fact = 1_u64;
+        for n in 0..=20 {
+            if n > 0 {
+                fact *= n;
+            }
+            assert_eq!(factor_factorial(n), if n < 2 { PrimeFactorization::new() } else { factor(fact) });
+        }
+    }
+
+    #[test]
+    fn small_binomials() {
+        // Pascal's triangle up to the point where the middle entries overflow
+        let mut row = vec![1_u64];
+        for n in 0..=66_u64 {
+            for (k, &c) in row.iter().enumerate() {
+                let pf = factor_binomial(n, k as u64);
+                assert_eq!(pf.product(), c, "C({}, {})", n, k);
+                for (p, e) in factor(c).iter() {
+                    assert_eq!(binomial_valuation(n, k as u64, p), e);
+                }
+            }
+            let mut next = vec![1];
+            next.extend(row.windows(2).map(|w| w[0] + w[1]));
+            next.push(1);
+            row = next;
+        }
+    }
+
+    #[test]
+    fn kummer_carries() {
+        // C(2n, n) is odd exactly when n is 0, and v_2 counts the 1 bits of n
+        let two = Prime::new(2).unwrap();
+        for n in 0..1000_u64 {
+            assert_eq!(binomial_valuation(2 * n, n, two), n.count_ones() as u64);
+        }
+        let p = Prime::new(1_000_003).unwrap();
+        assert_eq!(binomial_valuation(2_000_006, 1_000_003, p), 0);
+        assert_eq!(binomial_valuation(1_000_003, 1, p), 1);
+        assert_eq!(binomial_valuation(1_000_003 * 1_000_003, 1_000_003, p), 1);
+        assert_eq!(binomial_valuation(1_000_003 * 1_000_003, 1, p), 2);
+    }
+
+    #[test]
+    fn lucas() {
+        let mut row = vec![1_u64];
+        for n in 0..=66_u64 {
+            for p in [2, 3, 5, 7, 11, 13, 31, 61, 67].iter() {
+                let prime = Prime::new(*p).unwrap();
+                for (k, &c) in row.iter().enumerate() {
+                    assert_eq!(binomial_mod_prime(n, k as u64, prime), c % p, "C({}, {}) mod {}", n, k, p);
+                }
+                assert_eq!(binomial_mod_prime(n, n + 1, prime), 0);
+            }
+            let mut next = vec![1];
+            next.extend(row.windows(2).map(|w| w[0] + w[1]));
+            next.push(1);
+            row = next;
+        }
+        // single and double digit cases with big primes, and C(2p, p) ≡ 2 (mod p)
+        let p = Prime::new(MAX_U64_PRIME).unwrap();
+        assert_eq!(binomial_mod_prime(u64::MAX, 1, p), u64::MAX % MAX_U64_PRIME);
+        assert_eq!(binomial_mod_prime(MAX_U64_PRIME, MAX_U64_PRIME, p), 1);
+        assert_eq!(binomial_mod_prime(MAX_U64_PRIME, 1, p), 0);
+        let q = Prime::new(1_000_003).unwrap();
+        assert_eq!(binomial_mod_prime(2_000_006, 1_000_003, q), 2);
+    }
+
+    #[test]
+    fn factorials_mod_p() {
+        for p in [2_u64, 3, 5, 7, 11, 101, 997].iter() {
+            let prime = Prime::new(*p).unwrap();
+            let mut fact = 1 % p;
+            for n in 0..p + 5 {
+                if n > 0 {
+                    fact = fact * n % p;
+                }
+                assert_eq!(factorial_mod_prime(n, prime), fact, "{}! mod {}", n, p);
+            }
+        }
+        let p = Prime::new(1_000_000_007).unwrap();
+        assert_eq!(factorial_mod_prime(1_000_000_006, p), 1_000_000_006);
+        assert_eq!(factorial_mod_prime(1_000_000_007, p), 0);
+        assert_eq!(factorial_mod_prime(20, p), 146_326_063);
+    }
+
+    #[test]
+    fn wilson_quotients() {
+        let wilson: Vec<u64> = CertIter::all()
+            .take_while(|p| p.get() < 1000)
+            .filter(|&p| wilson_quotient(p) == 0)
+            .map(|p| p.get())
+            .collect();
+        assert_eq!(wilson, vec![5, 13, 563]);
+        // OEIS A007619, Wilson quotients themselves
+        let two = Prime::new(2).unwrap();
+        let seven = Prime::new(7).unwrap();
+        assert_eq!(wilson_quotient(two), 1);
+        assert_eq!(wilson_quotient(seven), 103 % 7);
+    }
+
+    #[test]
+    fn primorials() {
+        // OEIS A034386
+        let small: Vec<u64> = (0..=12).map(primorial).collect();
+        assert_eq!(small, vec![1, 1, 2, 6, 6, 30, 30, 210, 210, 210, 210, 2310, 2310]);
+        assert_eq!(primorial(52), 614_889_782_588_491_410);
+        assert_eq!(checked_primorial(53), None);
+        assert_eq!(checked_primorial(u64::MAX), None);
+        assert_eq!(checked_primorial_u128(52), Some(614_889_782_588_491_410));
+        assert_eq!(checked_primorial_u128(53), Some(614_889_782_588_491_410 * 53));
+        assert!(checked_primorial_u128(102).is_some());
+        assert_eq!(checked_primorial_u128(103), None);
+        for n in 0..=52 {
+            assert_eq!(factor_primorial(n).product(), primorial(n));
+            assert_eq!(factor_primorial(n).radical(), primorial(n));
+        }
+        assert_eq!(factor_primorial(100_000).iter().count() as u64, prime_pi(100_000));
+    }
+
+    #[test]
+    fn big_factorials() {
+        let n = 1_000_000;
+        let pf = factor_factorial(n);
+        assert_eq!(pf.iter().count() as u64, prime_pi(n));
+        // v_2(n!) = n - (number of 1 bits in n)
+        assert_eq!(pf.valuation(Prime::new(2).unwrap()), n - n.count_ones() as u64);
+        assert_eq!(pf.valuation(Prime::new(999_983).unwrap()), 1);
+        assert_eq!(pf.valuation(Prime::new(5).unwrap()), 249_998);
+    }
+}
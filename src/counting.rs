@@ -0,0 +1,582 @@
+use super::*;
+use super::sieve::{ inclusive_bounds, small_primes, SegmentSieve, SEGMENT_LEN };
+
+use std::ops::RangeBounds;
+
+/// Below this, prime_pi just sieves.
+const PI_SIEVE_LIMIT: u64 = 10_000_000;
+
+/// The number of leading primes whose multiples `phi_tiny` removes with a lookup table, so
+/// the table covers 2·3·5·7·11·13 = 30030.
+const PHI_TINY_PRIMES: usize = 6;
+
+/// Prime counting function
+///
+/// Calculates π(x), the number of primes less than or equal to `x`.
+///
+/// Below 10^7 the primes are simply sieved.  Larger `x` use the Lagarias-Miller-Odlyzko
+/// version of the Meissel-Lehmer method, which takes O(x^(2/3)) time.  Its tables hold about 11
+/// bytes for each number up to a bound of x^(1/3) ln(x) / 8, so the memory is a few megabytes up
+/// to 10^14 but grows to around 150 MB near 2^64.  This counts the primes below 10^12 in about a
+/// second, which would take hours with `PrimeIter`.  The time grows by a factor of about 4.6 per
+/// decade, so 10^14 takes under half a minute and values near 2^64 most of a day.
+pub fn prime_pi(x: u64) -> u64 {
+    if x < PI_SIEVE_LIMIT {
+        return count_by_sieve(0, x);
+    }
+    pi_lmo(x)
+}
+
+/// Counts the primes up to `x` by the Lagarias-Miller-Odlyzko algorithm.
+///
+/// With `y >= x^(1/3)` and `a = π(y)`, `π(x) = φ(x, a) + a - 1 - P2(x, a)`, where `φ(x, a)`
+/// counts the numbers up to `x` with no prime factor among the first `a` primes and `P2(x, a)`
+/// counts those that are a product of two primes above `y`.  Expanding `φ(x, a)` by the
+/// recurrence `φ(x, b) = φ(x, b - 1) - φ(x / p_b, b - 1)` down to the leaves at `y` splits it
+/// into the ordinary leaves `S1` and the special leaves `S2`.
+fn pi_lmo(x: u64) -> u64 {
+    let y = lmo_y(x);
+    let primes = small_primes(y);
+    let a = primes.len();
+    let c = a.min(PHI_TINY_PRIMES);
+    let (lpf, mu) = least_factors_and_mobius(y);
+    let phi_tiny = PhiTiny::new(&primes[..c]);
+
+    // S1: the ordinary leaves μ(n) φ(x / n, c) for squarefree n <= y with no factor below p_c+1
+    let p_c = primes[..c].last().copied().unwrap_or(1);
+    let mut phi: i128 = 0;
+    for n in 1..=y as usize {
+        if mu[n] != 0 && u64::from(lpf[n]) > p_c {
+            phi += i128::from(mu[n]) * i128::from(phi_tiny.phi(x / n as u64));
+        }
+    }
+    phi += special_leaves(x, y, &primes, c, &lpf, &mu);
+    let pi = phi + a as i128 - 1 - i128::from(p2(x, y, a as u64));
+    pi as u64
+}
+
+/// Chooses the leaf bound `y` for `pi_lmo()`.  It has to be at least x^(1/3); going somewhat
+/// higher shrinks the sieving interval `x / y` for the special leaves and P2.
+fn lmo_y(x: u64) -> u64 {
+    let alpha = ((x as f64).ln() / 8.0).max(1.0) as u64;
+    (icbrt(x) * alpha).min(isqrt(x))
+}
+
+/// Finds the least prime factor and Möbius function of every number up to `y`.  The least
+/// prime factor of 1 is given as `u32::MAX`, to compare above every prime.
+fn least_factors_and_mobius(y: u64) -> (Vec<u32>, Vec<i8>) {
+    let y = y as usize;
+    let mut lpf = vec![0_u32; y + 1];
+    let mut mu = vec![1_i8; y + 1];
+    for i in 2..=y {
+        if lpf[i] != 0 {
+            continue;
+        }
+        for j in (i..=y).step_by(i) {
+            if lpf[j] == 0 {
+                lpf[j] = i as u32;
+            }
+            mu[j] = -mu[j];
+        }
+        if let Some(sq) = i.checked_mul(i).filter(|&sq| sq <= y) {
+            for j in (sq..=y).step_by(sq) {
+                mu[j] = 0;
+            }
+        }
+    }
+    if y >= 1 {
+        lpf[1] = u32::MAX;
+    }
+    (lpf, mu)
+}
+
+/// Evaluates φ(v, c) for a fixed small `c`, using the periodicity of the numbers coprime to the
+/// first `c` primes.
+struct PhiTiny {
+    modulus: u64,
+    /// coprime_upto[r] counts the numbers in 1..=r coprime to the modulus.
+    coprime_upto: Vec<u32>,
+}
+
+impl PhiTiny {
+    fn new(primes: &[u64]) -> Self {
+        let modulus: u64 = primes.iter().product();
+        let mut coprime_upto = vec![0_u32; modulus as usize + 1];
+        for r in 1..=modulus as usize {
+            let coprime = primes.iter().all(|&p| !(r as u64).is_multiple_of(p));
+            coprime_upto[r] = coprime_upto[r - 1] + coprime as u32;
+        }
+        PhiTiny { modulus, coprime_upto }
+    }
+
+    fn phi(&self, v: u64) -> u64 {
+        let per_period = u64::from(self.coprime_upto[self.modulus as usize]);
+        v / self.modulus * per_period + u64::from(self.coprime_upto[(v % self.modulus) as usize])
+    }
+}
+
+/// A Fenwick tree over a sieve segment, counting the numbers not yet crossed off.
+struct Fenwick {
+    tree: Vec<u32>,
+}
+
+impl Fenwick {
+    fn new(alive: &[bool]) -> Self {
+        let mut tree: Vec<u32> = alive.iter().map(|&b| b as u32).collect();
+        for i in 0..tree.len() {
+            let j = i | (i + 1);
+            if j < tree.len() {
+                tree[j] += tree[i];
+            }
+        }
+        Fenwick { tree }
+    }
+
+    fn remove(&mut self, mut i: usize) {
+        while i < self.tree.len() {
+            self.tree[i] -= 1;
+            i |= i + 1;
+        }
+    }
+
+    /// Counts the live entries in `0..=i`.
+    fn prefix(&self, mut i: usize) -> u64 {
+        let mut count = 0;
+        loop {
+            count += u64::from(self.tree[i]);
+            i &= i + 1;
+            if i == 0 {
+                return count;
+            }
+            i -= 1;
+        }
+    }
+}
+
+/// Sums the special leaves `-μ(m) φ(x / (p_b m), b - 1)`, for `b > c` and squarefree `m <= y`
+/// with `m p_b > y` and no prime factor up to `p_b`.
+///
+/// Every such `x / (p_b m)` is below `x / y`, so that interval is sieved one segment at a time,
+/// crossing off the multiples of each prime in turn.  While the multiples of the first `b - 1`
+/// primes are gone, the leaves for `p_b` landing in the segment are counted with a Fenwick tree,
+/// plus a running count of the survivors in the earlier segments.
+fn special_leaves(x: u64, y: u64, primes: &[u64], c: usize, lpf: &[u32], mu: &[i8]) -> i128 {
+    let limit = x / (y + 1);
+    let seg_len = y.max(1 << 12);
+    // survivors[b] counts the numbers below the current segment free of the first b primes
+    let mut survivors = vec![0_u64; primes.len()];
+    let mut alive = vec![true; seg_len as usize];
+    let mut sum: i128 = 0;
+    let mut low = 1;
+    while low <= limit {
+        let high = (low + seg_len).min(limit + 1);
+        let len = (high - low) as usize;
+        let alive = &mut alive[..len];
+        alive.iter_mut().for_each(|b| *b = true);
+        for &p in &primes[..c] {
+            for i in (first_multiple(low, p) - low..high - low).step_by(p as usize) {
+                alive[i as usize] = false;
+            }
+        }
+        let mut tree = Fenwick::new(alive);
+        let mut remaining = alive.iter().filter(|&&b| b).count() as u64;
+        for (b, &p) in primes.iter().enumerate().skip(c) {
+            // leaves with low <= x / (p m) < high
+            let m_lo = (y / p).max((u128::from(x) / (u128::from(high) * u128::from(p))) as u64) + 1;
+            let m_hi = y.min(x / (low * p));
+            for m in m_lo..=m_hi {
+                let m = m as usize;
+                if mu[m] != 0 && u64::from(lpf[m]) > p {
+                    let v = x / (p * m as u64);
+                    let count = survivors[b] + tree.prefix((v - low) as usize);
+                    sum -= i128::from(mu[m]) * i128::from(count);
+                }
+            }
+            survivors[b] += remaining;
+            for i in (first_multiple(low, p) - low..high - low).step_by(p as usize) {
+                let i = i as usize;
+                if alive[i] {
+                    alive[i] = false;
+                    tree.remove(i);
+                    remaining -= 1;
+                }
+            }
+        }
+        low = high;
+    }
+    sum
+}
+
+/// The first multiple of `p` at or above `low`.
+fn first_multiple(low: u64, p: u64) -> u64 {
+    low.div_ceil(p) * p
+}
+
+/// Calculates `P2(x, a) = Σ (π(x / p) - π(p) + 1)` over the primes `y < p <= sqrt(x)`, which
+/// counts the numbers up to `x` that are a product of two primes above `y`.
+///
+/// The primes `p` are taken in descending order, so the `x / p` ascend, and their π values come
+/// from a count kept while sieving upwards from `y` to `x / y`.
+fn p2(x: u64, y: u64, a: u64) -> u64 {
+    let sqrt_x = isqrt(x);
+    if y >= sqrt_x {
+        return 0;
+    }
+    let limit = x / (y + 1);
+    let sieve = SegmentSieve::new(limit);
+    // the primes in up_lo..=up_hi, with up_base primes below up_lo
+    let (mut up_base, mut up_hi) = (a, y);
+    let mut up = Vec::new();
+    let mut down = Vec::new();
+    let mut sum = 0;
+    let mut count = 0;
+    let mut hi = sqrt_x;
+    while hi > y {
+        let lo = hi.saturating_sub(SEGMENT_LEN - 1).max(y + 1);
+        down.clear();
+        sieve.primes_in(lo, hi, &mut down);
+        for &p in down.iter().rev() {
+            let v = x / p;
+            while v > up_hi {
+                up_base += up.len() as u64;
+                let up_lo = up_hi + 1;
+                up_hi = (up_lo + SEGMENT_LEN - 1).min(limit);
+                up.clear();
+                sieve.primes_in(up_lo, up_hi, &mut up);
+            }
+            sum += up_base + up.partition_point(|&q| q <= v) as u64;
+            count += 1;
+        }
+        hi = lo - 1;
+    }
+    // the primes p are p_(a+1), ..., p_(a+count), and π(p) - 1 sums to a + ... + (a+count-1)
+    sum - (count * a + count * (count - 1) / 2)
+}
+
+/// The number of primes that fit in a u64.
+const PI_U64_MAX: u64 = 425_656_284_035_217_743;
+
+/// The logarithmic integral li(x), by Ramanujan's series.
+pub(crate) fn li(x: f64) -> f64 {
+    const EULER_GAMMA: f64 = 0.577_215_664_901_532_9;
+    if x <= 1.0 {
+        return f64::NEG_INFINITY;
+    }
+    let ln_x = x.ln();
+    let mut sum = 0.0;
+    let mut term = 1.0;  // (-1)^(n-1) (ln x)^n / (n! 2^(n-1))
+    let mut inner = 0.0;  // 1 + 1/3 + ... + 1/(2k+1) for k <= (n-1)/2
+    for n in 1..200 {
+        term *= if n == 1 { ln_x } else { -ln_x / (n as f64 * 2.0) };
+        if n % 2 == 1 {
+            inner += 1.0 / n as f64;
+        }
+        sum += term * inner;
+        if term.abs() < 1e-18 * sum.abs() {
+            break;
+        }
+    }
+    EULER_GAMMA + ln_x.ln() + x.sqrt() * sum
+}
+
+/// Cheap lower and upper bounds on π(x).
+///
+/// The lower bound is Dusart's `x/ln x (1 + 1/ln x)` for `x >= 599`, or Rosser's `x/ln x` from
+/// 17 up.  The upper bound is the smaller of Dusart's `x/ln x (1 + 1.2762/ln x)` and li(x), which
+/// exceeds π(x) for every x up to 10^19.  Both are rounded outward to absorb floating point error.
+pub(crate) fn prime_pi_bounds(x: u64) -> (u64, u64) {
+    if x < 17 {
+        return (0, 6);
+    }
+    let xf = x as f64;
+    let ln_x = xf.ln();
+    let lower = if x >= 599 {
+        xf / ln_x * (1.0 + 1.0 / ln_x)
+    } else {
+        xf / ln_x
+    };
+    let mut upper = xf / ln_x * (1.0 + 1.2762 / ln_x);
+    if x <= 10_000_000_000_000_000_000 {
+        upper = upper.min(li(xf));
+    }
+    ((lower * (1.0 - 1e-12) - 1.0).max(0.0) as u64, (upper * (1.0 + 1e-12) + 1.0) as u64)
+}
+
+/// Solves li(x) = n for x by Newton's method.
+fn li_inverse(n: f64) -> f64 {
+    let mut x = n * n.ln().max(1.0);
+    for _ in 0..20 {
+        let next = x - (li(x) - n) * x.ln();
+        if (next - x).abs() < 1.0 {
+            return next;
+        }
+        x = next.max(2.0);
+    }
+    x
+}
+
+/// Returns the `n`-th prime, counting 2 as the first.
+///
+/// Returns `None` for `n = 0`, or if the `n`-th prime is larger than u64 can represent.
+///
+/// Rather than generating every smaller prime, this picks a point `x` just below the `n`-th prime
+/// by inverting the logarithmic integral, counts the primes up to `x` with `prime_pi()`, and
+/// sieves forward from there.  The cost is dominated by `prime_pi()`, so finding the 10^10-th
+/// prime takes well under a second.
+pub fn nth_prime(n: u64) -> Option<u64> {
+    if n == 0 || n > PI_U64_MAX {
+        return None;
+    }
+    // 6542 primes, enough that the estimate below is never too close to 2
+    let small = small_primes(1 << 16);
+    if n <= small.len() as u64 {
+        return Some(small[n as usize - 1]);
+    }
+    let est = li_inverse(n as f64);
+    // |π(x) - li(x)| is comfortably below sqrt(x) ln(x) / 8 in this range, so back off by a
+    // little more than that to land below the answer.
+    let mut margin = (est.sqrt() * est.ln() / 4.0).max(1000.0);
+    let mut x;
+    let mut count;
+    loop {
+        x = (est - margin).clamp(2.0, u64::MAX as f64) as u64;
+        count = prime_pi(x);
+        if count < n {
+            break;
+        }
+        margin *= 2.0;
+    }
+    let mut res = None;
+    let sieve = SegmentSieve::new((est + margin).min(u64::MAX as f64) as u64);
+    sieve.for_segments(x + 1, u64::MAX, |primes| {
+        let needed = (n - count) as usize;
+        if needed <= primes.len() {
+            res = Some(primes[needed - 1]);
+            false
+        } else {
+            count += primes.len() as u64;
+            true
+        }
+    });
+    res
+}
+
+/// Returns the index of the prime `p`, so that `p` is the `k`-th prime, counting 2 as the first.
+///
+/// Returns `None` if `p` is not prime.  This is the inverse of `nth_prime()`, and costs one call
+/// to `prime_pi()`.
+pub fn prime_index(p: u64) -> Option<u64> {
+    if is_u64_prime(p) {
+        Some(prime_pi(p))
+    } else {
+        None
+    }
+}
+
+/// Counts the primes in `lo..=hi` by sieving the whole range.
+fn count_by_sieve(lo: u64, hi: u64) -> u64 {
+    let mut count = 0;
+    SegmentSieve::new(hi).for_segments(lo, hi, |primes| {
+        count += primes.len() as u64;
+        true
+    });
+    count
+}
+
+/// Counts the primes in `lo..=hi` as a difference of two prime_pi values.
+fn count_by_pi(lo: u64, hi: u64) -> u64 {
+    prime_pi(hi) - if lo > 0 { prime_pi(lo - 1) } else { 0 }
+}
+
+/// Counts the primes in a range.
+///
+/// Narrow ranges are sieved directly, while wide ones are counted as π(b) - π(a - 1) using the
+/// sublinear `prime_pi()`, whichever is expected to be cheaper.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::count_primes_in;
+///
+/// fn main() {
+///     assert_eq!(count_primes_in(10..20), 4);
+///     assert_eq!(count_primes_in(..=1_000_000_000), 50_847_534);
+/// }
+/// ```
+pub fn count_primes_in<R: RangeBounds<u64>>(range: R) -> u64 {
+    let (lo, hi) = match inclusive_bounds(&range) {
+        Some(b) => b,
+        None => return 0,
+    };
+    if hi < PI_SIEVE_LIMIT {
+        // prime_pi() would sieve from 0 anyway
+        return count_by_sieve(lo, hi);
+    }
+    let width = (hi - lo) as f64 + 1.0;
+    // prime_pi() takes O(x^(2/3)) steps, each costing several times a sieve step
+    let pi_cost = 5.0 * (hi as f64).powf(2.0 / 3.0);
+    // Above 2^40 the sieve leaves survivors that need individual Miller-Rabin tests.
+    let sieve_cost = if hi >= 1 << 40 { width * 10.0 } else { width };
+    if sieve_cost <= pi_cost {
+        count_by_sieve(lo, hi)
+    } else {
+        count_by_pi(lo, hi)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_prime_pi() {
+        use primal::Sieve;
+        let limit = 12_000_000;
+        let sieve = Sieve::new(limit);
+        let mut x = 0;
+        while x < limit {
+            assert_eq!(prime_pi(x as u64), sieve.prime_pi(x) as u64, "prime_pi({})", x);
+            x = x * 5 / 4 + 1;
+        }
+        for x in PI_SIEVE_LIMIT - 10..PI_SIEVE_LIMIT + 10 {
+            assert_eq!(prime_pi(x), sieve.prime_pi(x as usize) as u64, "prime_pi({})", x);
+        }
+    }
+
+    #[test]
+    fn compare_lmo() {
+        use primal::Sieve;
+        let limit = 3_000_000;
+        let sieve = Sieve::new(limit);
+        for x in 2..2000 {
+            assert_eq!(pi_lmo(x as u64), sieve.prime_pi(x) as u64, "pi_lmo({})", x);
+        }
+        let mut x = 2000;
+        while x < limit {
+            assert_eq!(pi_lmo(x as u64), sieve.prime_pi(x) as u64, "pi_lmo({})", x);
+            x = x * 9 / 8 + 7;
+        }
+        // around cubes and squares, where y and sqrt(x) change
+        for r in [100_u64, 101, 139, 140] {
+            for x in [r * r * r - 1, r * r * r, r * r * r + 1] {
+                assert_eq!(pi_lmo(x), sieve.prime_pi(x as usize) as u64, "pi_lmo({})", x);
+            }
+        }
+        for r in [1000_u64, 1009, 1500] {
+            for x in [r * r - 1, r * r, r * r + 1] {
+                assert_eq!(pi_lmo(x), sieve.prime_pi(x as usize) as u64, "pi_lmo({})", x);
+            }
+        }
+    }
+
+    #[test]
+    fn lmo_helpers() {
+        let (lpf, mu) = least_factors_and_mobius(1000);
+        for n in 2..=1000_u64 {
+            let pf = factor(n);
+            assert_eq!(u64::from(lpf[n as usize]), pf.iter().next().unwrap().0.get());
+            assert_eq!(i64::from(mu[n as usize]), pf.mobius());
+        }
+        assert_eq!((lpf[1], mu[1]), (u32::MAX, 1));
+        let primes = small_primes(13);
+        for c in 0..=PHI_TINY_PRIMES {
+            let tiny = PhiTiny::new(&primes[..c]);
+            let mut expected = 0;
+            for v in 0..100_000_u64 {
+                if v > 0 && primes[..c].iter().all(|&p| !v.is_multiple_of(p)) {
+                    expected += 1;
+                }
+                assert_eq!(tiny.phi(v), expected, "phi({}, {})", v, c);
+            }
+        }
+        let mut alive: Vec<bool> = (0..1000).map(|i| i % 3 != 0).collect();
+        let mut tree = Fenwick::new(&alive);
+        for i in (0..1000).step_by(7) {
+            if alive[i] {
+                alive[i] = false;
+                tree.remove(i);
+            }
+        }
+        for i in 0..1000 {
+            assert_eq!(tree.prefix(i), alive[..=i].iter().filter(|&&b| b).count() as u64);
+        }
+    }
+
+    #[test]
+    fn compare_nth_prime() {
+        use primal::Primes;
+        assert_eq!(nth_prime(0), None);
+        let mut next = 1;
+        for (i, p) in Primes::all().take(2_000_000).enumerate() {
+            let n = i as u64 + 1;
+            if n == next {
+                assert_eq!(nth_prime(n), Some(p as u64), "nth_prime({})", n);
+                next = next * 9 / 8 + 1;
+            }
+        }
+    }
+
+    #[test]
+    fn big_nth_prime() {
+        assert_eq!(nth_prime(100_000_000), Some(2_038_074_743));
+        assert_eq!(nth_prime(203_280_221), Some(4_294_967_291));
+        assert_eq!(nth_prime(PI_U64_MAX + 1), None);
+    }
+
+    #[test]
+    fn prime_indices() {
+        assert_eq!(prime_index(2), Some(1));
+        assert_eq!(prime_index(1), None);
+        assert_eq!(prime_index(4), None);
+        for &n in [3, 1000, 6543, 123_456, 10_000_000].iter() {
+            let p = nth_prime(n).unwrap();
+            assert_eq!(prime_index(p), Some(n));
+            assert_eq!(prime_index(p + 1), None);
+        }
+    }
+
+    #[test]
+    fn compare_range_counts() {
+        let ranges = [(0, 0), (0, 2), (2, 2), (3, 4), (100, 100_000), (1_000_000, 1_500_000),
+                      (65_000, 70_000), (1 << 32, (1 << 32) + 100_000)];
+        for &(lo, hi) in ranges.iter() {
+            let c = count_by_sieve(lo, hi);
+            assert_eq!(c, count_by_pi(lo, hi), "counting primes in {}..={}", lo, hi);
+            assert_eq!(c, count_primes_in(lo..=hi), "counting primes in {}..={}", lo, hi);
+        }
+        assert_eq!(count_primes_in(u64::MAX - 1000..), 21);
+        assert_eq!(count_primes_in(10..10), 0);
+        assert_eq!(count_primes_in(..100), 25);
+    }
+
+    #[test]
+    fn li_values() {
+        assert!((li(2.0) - 1.045_163_780_117_492_7).abs() < 1e-12);
+        assert!((li(1e10) - 455_055_614.586_1).abs() < 1e-2);
+    }
+
+    #[test]
+    fn pi_bounds() {
+        let mut count = 0;
+        for x in 0..1_000_000 {
+            if is_u64_prime(x) {
+                count += 1;
+            }
+            let (lo, hi) = prime_pi_bounds(x);
+            assert!(lo <= count && count <= hi, "π({}) = {} not in {}..={}", x, count, lo, hi);
+        }
+        for &(x, pi) in [(1_000_000_000, 50_847_534), (10_000_000_000, 455_052_511),
+                         (1 << 32, 203_280_221), (u64::MAX, PI_U64_MAX)].iter() {
+            let (lo, hi) = prime_pi_bounds(x);
+            assert!(lo <= pi && pi <= hi, "π({}) = {} not in {}..={}", x, pi, lo, hi);
+        }
+    }
+
+    #[test]
+    fn big_prime_pi() {
+        assert_eq!(prime_pi(1_000_000_000), 50_847_534);
+        assert_eq!(prime_pi(1 << 32), 203_280_221);
+        assert_eq!(prime_pi(10_000_000_000), 455_052_511);
+        assert_eq!(prime_pi(100_000_000_000), 4_118_054_813);
+    }
+}
@@ -0,0 +1,317 @@
+//! Prime generation for cryptographic prototyping.
+//!
+//! `gen_safe_prime()` and `gen_prime_congruent()` pick uniformly among the primes with the
+//! requested properties, using `rand`'s unbiased range sampling and rejection, so no qualifying
+//! prime is favored over another.  Small search spaces are enumerated instead, so that impossible
+//! requests return `None` rather than looping forever.  `gen_blum_integer()` and
+//! `gen_strong_prime()` are not uniform; their docs describe the bias.
+//!
+//! These are limited to the u64 range, which is far too small for real cryptography.  They are
+//! meant for tests, toy protocols and experiments.
+//!
+//! Requires the `rand` feature.
+
+use super::*;
+
+use super::modular::pow_mod;
+
+use rand::Rng;
+
+/// Search spaces with at most this many candidates are enumerated rather than sampled.
+const ENUMERATE_LIMIT: u64 = 1 << 12;
+
+/// Chooses uniformly among the `x = residue + k * modulus` in `lo..=hi` for which `accept(x)`
+/// holds, or returns `None` if there are none.
+///
+/// Large search spaces are sampled until an accepted value turns up, so they must contain one.
+pub(crate) fn sample_progression<G, F>(lo: u64, hi: u64, residue: u64, modulus: u64, rng: &mut G,
+                                       accept: F) -> Option<u64>
+    where G: Rng + ?Sized, F: Fn(u64) -> bool
+{
+    let residue = residue % modulus;
+    // the first candidate at or above lo
+    let offset = (residue as u128 + modulus as u128 - (lo % modulus) as u128) % modulus as u128;
+    let first = lo.checked_add(offset as u64)?;
+    if first > hi {
+        return None;
+    }
+    let count = (hi - first) / modulus + 1;
+    if count <= ENUMERATE_LIMIT {
+        let found: Vec<u64> = (0..count).map(|k| first + k * modulus).filter(|&x| accept(x)).collect();
+        if found.is_empty() {
+            None
+        } else {
+            Some(found[rng.gen_range(0..found.len())])
+        }
+    } else {
+        loop {
+            let x = first + rng.gen_range(0..count) * modulus;
+            if accept(x) {
+                return Some(x);
+            }
+        }
+    }
+}
+
+/// The range of numbers with exactly `bits` bits.
+fn bit_range(bits: u32) -> (u64, u64) {
+    let lo = 1_u64 << (bits - 1);
+    (lo, lo - 1 + lo)
+}
+
+/// Generates a random safe prime with exactly `bits` bits: a prime `p` such that `(p - 1) / 2`
+/// is also prime.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::{ crypto, is_u64_prime };
+/// use rand::SeedableRng;
+///
+/// fn main() {
+///     let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+///     let p = crypto::gen_safe_prime(32, &mut rng).get();
+///     assert_eq!(64 - p.leading_zeros(), 32);
+///     assert!(is_u64_prime((p - 1) / 2));
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `bits` is not between 3 and 64.
+pub fn gen_safe_prime<G: Rng + ?Sized>(bits: u32, rng: &mut G) -> Prime {
+    assert!((3..=64).contains(&bits), "gen_safe_prime called with {} bits", bits);
+    let (lo, hi) = bit_range(bits);
+    // p = 2q + 1, so q ranges over half of the odd p
+    let q = sample_progression(lo / 2, (hi - 1) / 2, 0, 1, rng, |q| {
+        is_u64_prime(q) && is_u64_prime(2 * q + 1)
+    });
+    Prime::new(2 * q.unwrap() + 1).unwrap()
+}
+
+/// Generates a random prime with exactly `bits` bits that is congruent to `residue` modulo
+/// `modulus`.
+///
+/// Returns `None` when there is no such prime.  Apart from small ranges, which are checked
+/// exhaustively, `residue` must be coprime to `modulus` for this to be possible, and otherwise
+/// `None` is returned immediately.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::crypto;
+/// use rand::SeedableRng;
+///
+/// fn main() {
+///     let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+///     let p = crypto::gen_prime_congruent(40, 1, 1 << 20, &mut rng).unwrap().get();
+///     assert_eq!(p % (1 << 20), 1);
+///     assert_eq!(crypto::gen_prime_congruent(40, 4, 6, &mut rng), None);
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `bits` is not between 2 and 64, or if `modulus` is 0.
+pub fn gen_prime_congruent<G>(bits: u32, residue: u64, modulus: u64, rng: &mut G) -> Option<Prime>
+    where G: Rng + ?Sized
+{
+    use num::Integer;
+    assert!((2..=64).contains(&bits), "gen_prime_congruent called with {} bits", bits);
+    assert!(modulus > 0, "gen_prime_congruent called with modulus 0");
+    let (lo, hi) = bit_range(bits);
+    let count = (hi - lo) / modulus + 1;
+    if count > ENUMERATE_LIMIT && residue.gcd(&modulus) != 1 {
+        return None;
+    }
+    sample_progression(lo, hi, residue, modulus, rng, is_u64_prime).and_then(Prime::new)
+}
+
+/// Generates a random Blum integer with exactly `bits` bits, returned with its two prime factors
+/// `p < q`.
+///
+/// A Blum integer is the product of two distinct primes that are both congruent to 3 modulo 4.
+/// Up to 16 bits, one is chosen uniformly from a complete list.  Beyond that, `p` and `q` are
+/// drawn with `gen_prime_congruent()` at about half the size each, retrying until their product
+/// has the right length.
+///
+/// Above 16 bits only balanced products are produced: `p` has `bits / 2` bits and `q` the rest,
+/// so Blum integers with one small factor never come up, and the rest are not equally likely.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::{ crypto, is_blum_integer };
+/// use rand::SeedableRng;
+///
+/// fn main() {
+///     let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+///     let (n, p, q) = crypto::gen_blum_integer(48, &mut rng);
+///     assert_eq!(n, p.get() * q.get());
+///     assert!(is_blum_integer(n));
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `bits` is not between 5 and 64, since 21 is the smallest Blum integer.
+pub fn gen_blum_integer<G: Rng + ?Sized>(bits: u32, rng: &mut G) -> (u64, Prime, Prime) {
+    assert!((5..=64).contains(&bits), "gen_blum_integer called with {} bits", bits);
+    let (lo, hi) = bit_range(bits);
+    if bits <= 16 {
+        let n = sample_progression(lo, hi, 1, 4, rng, is_blum_integer).unwrap();
+        let facs: Vec<Prime> = factor(n).iter().map(|(p, _)| p).collect();
+        return (n, facs[0], facs[1]);
+    }
+    let p_bits = bits / 2;
+    loop {
+        let p = gen_prime_congruent(p_bits, 3, 4, rng).unwrap();
+        let q = gen_prime_congruent(bits - p_bits, 3, 4, rng).unwrap();
+        if p == q {
+            continue;
+        }
+        if let Some(n) = p.get().checked_mul(q.get()) {
+            if n >= lo {
+                return (n, p.min(q), p.max(q));
+            }
+        }
+    }
+}
+
+/// Generates a random strong prime with exactly `bits` bits, using Gordon's algorithm.
+///
+/// A strong prime `p` has a large prime factor `r` of `p - 1`, a large prime factor `s` of
+/// `p + 1`, and `r - 1` has a large prime factor `t`.  Gordon's algorithm draws random primes `s`
+/// and `t` of about `bits/2 - 4` and `bits/2 - 10` bits, finds the first prime `r = 2it + 1`,
+/// and then searches `p = p0 + 2jrs` for a prime, where `p0 = 2(s^(r-2) mod r)s - 1` is
+/// congruent to 1 modulo `r` and to -1 modulo `s`.  If no prime of the right length turns up,
+/// it starts over with new `s` and `t`.
+///
+/// The result is the first prime in the progression for the drawn `s` and `t`, so it is not
+/// uniform among strong primes: primes that follow a long gap in their progression are favored,
+/// and strong primes whose factors fall outside those sizes are never returned.
+///
+/// Within 64 bits the "large" factors are only a few dozen bits long, so this is a structural
+/// demonstration rather than a protection against `p - 1` or `p + 1` factoring methods.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::{ crypto, factor };
+/// use rand::SeedableRng;
+///
+/// fn main() {
+///     let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+///     let p = crypto::gen_strong_prime(64, &mut rng).get();
+///     let largest = |n: u64| factor(n).iter().map(|(q, _)| q.get()).max().unwrap();
+///     assert!(largest(p - 1) >= 1 << 22);
+///     assert!(largest(p + 1) >= 1 << 27);
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `bits` is not between 32 and 64.
+pub fn gen_strong_prime<G: Rng + ?Sized>(bits: u32, rng: &mut G) -> Prime {
+    assert!((32..=64).contains(&bits), "gen_strong_prime called with {} bits", bits);
+    let (lo, hi) = bit_range(bits);
+    loop {
+        let s = random_prime_bits(bits / 2 - 4, rng).get();
+        let t = random_prime_bits(bits / 2 - 10, rng).get();
+        let r = (1..).map(|i| 2 * i * t + 1).find(|&r| is_u64_prime(r)).unwrap();
+        if r == s {
+            continue;
+        }
+        let rs = r as u128 * s as u128;
+        let p0 = 2 * pow_mod(s, r - 2, r) as u128 * s as u128 - 1;
+        // the first p = p0 + 2jrs at or above lo
+        let step = 2 * rs;
+        let mut p = if p0 >= lo as u128 {
+            p0
+        } else {
+            p0 + (lo as u128 - p0).div_ceil(step) * step
+        };
+        while p <= hi as u128 {
+            if let Some(prime) = Prime::new(p as u64) {
+                return prime;
+            }
+            p += step;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn safe_primes() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for bits in 3..=64 {
+            for _ in 0..5 {
+                let p = gen_safe_prime(bits, &mut rng).get();
+                assert_eq!(64 - p.leading_zeros(), bits);
+                assert!(is_u64_prime((p - 1) / 2), "{} is not a safe prime", p);
+            }
+        }
+        // 5 and 7 are the only 3 bit safe primes
+        let small: std::collections::BTreeSet<u64> = (0..100).map(|_| gen_safe_prime(3, &mut rng).get()).collect();
+        assert_eq!(small.into_iter().collect::<Vec<u64>>(), vec![5, 7]);
+    }
+
+    #[test]
+    fn blum_integers() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for bits in 5..=64 {
+            for _ in 0..5 {
+                let (n, p, q) = gen_blum_integer(bits, &mut rng);
+                assert_eq!(64 - n.leading_zeros(), bits);
+                assert!(is_blum_integer(n), "{} is not a Blum integer", n);
+                assert!(p < q);
+                assert_eq!(p.get() * q.get(), n);
+            }
+        }
+    }
+
+    #[test]
+    fn strong_primes() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let primes = |n: u64| factor(n).iter().map(|(q, _)| q.get()).collect::<Vec<u64>>();
+        for bits in 32..=64 {
+            for _ in 0..5 {
+                let p = gen_strong_prime(bits, &mut rng).get();
+                assert_eq!(64 - p.leading_zeros(), bits);
+                let (s_min, t_min) = (1 << (bits / 2 - 5), 1 << (bits / 2 - 11));
+                assert!(primes(p + 1).iter().any(|&s| s >= s_min), "p + 1 for {}", p);
+                assert!(primes(p - 1).iter().any(|&r| primes(r - 1).iter().any(|&t| t >= t_min)),
+                        "p - 1 for {}", p);
+            }
+        }
+    }
+
+    #[test]
+    fn congruent_primes() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for bits in 2..=64 {
+            for &(r, m) in [(1, 4), (3, 4), (1, 1), (7, 30), (1, 1 << 20), (12345, 1_000_003)].iter() {
+                match gen_prime_congruent(bits, r, m, &mut rng) {
+                    Some(p) => {
+                        let p = p.get();
+                        assert_eq!(64 - p.leading_zeros(), bits);
+                        assert_eq!(p % m, r % m);
+                    }
+                    None => {
+                        let (lo, hi) = bit_range(bits);
+                        assert!((lo..=hi).all(|n| n % m != r % m || !is_u64_prime(n)));
+                    }
+                }
+            }
+        }
+        // 2 is the only prime that's 0 mod 2
+        assert_eq!(gen_prime_congruent(2, 0, 2, &mut rng).map(|p| p.get()), Some(2));
+        assert_eq!(gen_prime_congruent(30, 0, 2, &mut rng), None);
+        assert_eq!(gen_prime_congruent(64, MAX_U64_PRIME, u64::MAX, &mut rng).map(|p| p.get()), Some(MAX_U64_PRIME));
+    }
+}
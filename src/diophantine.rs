@@ -0,0 +1,700 @@
+use super::*;
+
+use super::modular::{ crt, inv_mod, mul_mod, sqrt_mod_prime };
+
+/// Counts the representations of `n` as a sum of two squares, `n = a^2 + b^2`.
+///
+/// Like the classical r2 function, this counts ordered pairs of integers, so signs and order
+/// matter: `5 = (±1)^2 + (±2)^2 = (±2)^2 + (±1)^2` gives 8.  From the factorization of `n`, it is
+/// 0 if some prime `p ≡ 3 (mod 4)` appears to an odd power, and otherwise 4 times the product of
+/// `e + 1` over the primes `p ≡ 1 (mod 4)` dividing `n` to the power `e`.
+///
+/// `r2(0)` is 1, for `0 = 0^2 + 0^2`.
+pub fn r2(n: u64) -> u64 {
+    if n == 0 {
+        return 1;
+    }
+    let mut res = 4;
+    for (p, e) in factor(n).iter() {
+        match p.get() & 3 {
+            1 => res *= e + 1,
+            3 if e & 1 == 1 => return 0,
+            _ => (),
+        }
+    }
+    res
+}
+
+/// Multiplies two Gaussian integers `a + bi` and `c + di`.
+fn gaussian_mul((a, b): (i128, i128), (c, d): (i128, i128)) -> (i128, i128) {
+    (a * c - b * d, a * d + b * c)
+}
+
+/// Writes a prime `p ≡ 1 (mod 4)` as `a^2 + b^2`, by the Hermite-Serret algorithm.
+///
+/// With `x^2 ≡ -1 (mod p)`, the Euclidean algorithm on `p` and `x` reaches `a` as the first
+/// remainder below `sqrt(p)`.
+fn prime_two_squares(p: u64) -> (u64, u64) {
+    let x = sqrt_mod_prime(p - 1, p).unwrap();
+    let limit = isqrt(p);
+    let (mut r0, mut r1) = (p, x);
+    while r1 > limit {
+        let r2 = r0 % r1;
+        r0 = r1;
+        r1 = r2;
+    }
+    let b = isqrt(p - r1 * r1);
+    debug_assert_eq!(r1 * r1 + b * b, p);
+    (r1, b)
+}
+
+/// Writes `n` as a sum of two squares, `n = a^2 + b^2` with `a <= b`, if that is possible.
+///
+/// Each prime `p ≡ 1 (mod 4)` dividing `n` is split as `p = a^2 + b^2` with the Hermite-Serret
+/// algorithm, using a square root of -1 modulo `p` found by Tonelli-Shanks, and these are
+/// multiplied together as Gaussian integers.  Returns `None` exactly when `r2(n)` is 0.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::two_square_decompose;
+///
+/// fn main() {
+///     assert_eq!(two_square_decompose(1_000_000_009), Some((3_747, 31_400)));
+///     assert_eq!(two_square_decompose(21), None);
+/// }
+/// ```
+pub fn two_square_decompose(n: u64) -> Option<(u64, u64)> {
+    if n == 0 {
+        return Some((0, 0));
+    }
+    let mut z = (1_i128, 0_i128);
+    for (p, e) in factor(n).iter() {
+        let p = p.get();
+        if p & 3 == 3 {
+            if e & 1 == 1 {
+                return None;
+            }
+            let q = p.pow(e as u32 / 2) as i128;
+            z = (z.0 * q, z.1 * q);
+        } else {
+            let (a, b) = if p == 2 { (1, 1) } else { prime_two_squares(p) };
+            for _ in 0..e {
+                z = gaussian_mul(z, (a as i128, b as i128));
+            }
+        }
+    }
+    let (a, b) = (z.0.unsigned_abs() as u64, z.1.unsigned_abs() as u64);
+    Some((a.min(b), a.max(b)))
+}
+
+/// Finds the Pythagorean triples `(a, b, c)` with `a^2 + b^2 = c^2`, `0 < a < b`, and the given
+/// hypotenuse `c`, sorted by `a`.  With `primitive` set, only those with `gcd(a, b) = 1` are
+/// returned.
+///
+/// Every such triple comes from a way of writing `c^2` as a sum of two squares.  These are
+/// generated as Gaussian integers of norm `c^2`: for each prime `p ≡ 1 (mod 4)` dividing `c` to
+/// the power `e`, with `p = π π̄`, the factor `π^i π̄^(2e - i)` is chosen for some `0 <= i <= 2e`,
+/// while the other primes contribute `p^e` up to a unit.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::pythagorean_triples_with_hypotenuse;
+///
+/// fn main() {
+///     assert_eq!(pythagorean_triples_with_hypotenuse(25, false), vec![(7, 24, 25), (15, 20, 25)]);
+///     assert_eq!(pythagorean_triples_with_hypotenuse(25, true), vec![(7, 24, 25)]);
+/// }
+/// ```
+pub fn pythagorean_triples_with_hypotenuse(c: u64, primitive: bool) -> Vec<(u64, u64, u64)> {
+    use num::Integer;
+    if c == 0 {
+        return Vec::new();
+    }
+    // Gaussian integers of norm c^2, built up one prime at a time
+    let mut zs = vec![(1_i128, 0_i128)];
+    for (p, e) in factor(c).iter() {
+        let p = p.get();
+        if p & 3 == 1 {
+            let (a, b) = prime_two_squares(p);
+            let pi = (a as i128, b as i128);
+            let pi_bar = (a as i128, -(b as i128));
+            let mut next = Vec::new();
+            for z in zs {
+                for i in 0..=2 * e {
+                    let mut w = z;
+                    for _ in 0..i {
+                        w = gaussian_mul(w, pi);
+                    }
+                    for _ in i..2 * e {
+                        w = gaussian_mul(w, pi_bar);
+                    }
+                    next.push(w);
+                }
+            }
+            zs = next;
+        } else {
+            let q = p.pow(e as u32) as i128;
+            for z in zs.iter_mut() {
+                *z = (z.0 * q, z.1 * q);
+            }
+        }
+    }
+    let mut res: Vec<(u64, u64, u64)> = zs
+        .into_iter()
+        .map(|(x, y)| (x.unsigned_abs() as u64, y.unsigned_abs() as u64))
+        .filter(|&(x, y)| x != 0 && y != 0)
+        .map(|(x, y)| (x.min(y), x.max(y), c))
+        .filter(|&(a, b, _)| !primitive || a.gcd(&b) == 1)
+        .collect();
+    res.sort_unstable();
+    res.dedup();
+    res
+}
+
+/// Finds the Pythagorean triples `(a, b, c)` with `a^2 + b^2 = c^2` where the leg `a` is given
+/// and `b > 0`, sorted by `b`.  With `primitive` set, only those with `gcd(a, b) = 1` are
+/// returned.
+///
+/// Each triple comes from a factorization `a^2 = (c - b)(c + b)` into two factors of the same
+/// parity, so the divisors of `a^2` are enumerated from the factorization of `a`.  The other leg
+/// and the hypotenuse can be close to `a^2 / 2`, so the triples are returned as u128 values.
+/// `a` may be larger than `b`.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::pythagorean_triples_with_leg;
+///
+/// fn main() {
+///     assert_eq!(pythagorean_triples_with_leg(12, false), vec![(12, 5, 13), (12, 9, 15), (12, 16, 20), (12, 35, 37)]);
+///     assert_eq!(pythagorean_triples_with_leg(12, true), vec![(12, 5, 13), (12, 35, 37)]);
+/// }
+/// ```
+pub fn pythagorean_triples_with_leg(a: u64, primitive: bool) -> Vec<(u128, u128, u128)> {
+    use num::Integer;
+    if a == 0 {
+        return Vec::new();
+    }
+    let a2 = a as u128 * a as u128;
+    let mut divisors = vec![1_u128];
+    for (p, e) in factor(a).iter() {
+        let p = p.get() as u128;
+        let mut next = Vec::with_capacity(divisors.len() * (2 * e as usize + 1));
+        for &d in divisors.iter() {
+            let mut pd = d;
+            next.push(pd);
+            for _ in 0..2 * e {
+                pd *= p;
+                next.push(pd);
+            }
+        }
+        divisors = next;
+    }
+    let mut res: Vec<(u128, u128, u128)> = divisors
+        .into_iter()
+        .filter(|&d1| d1 < a2 / d1)
+        .map(|d1| (d1, a2 / d1))
+        .filter(|&(d1, d2)| (d2 - d1) & 1 == 0)
+        .map(|(d1, d2)| (a as u128, (d2 - d1) / 2, (d2 + d1) / 2))
+        .filter(|&(a, b, _)| !primitive || a.gcd(&b) == 1)
+        .collect();
+    res.sort_unstable();
+    res
+}
+
+/// Finds every square root of `a` modulo the prime power `p^e`.
+///
+/// Roots of units modulo odd primes are lifted with Hensel's lemma.  Otherwise, for `p = 2` or
+/// `p` dividing `a`, each root modulo `p^k` is lifted by trying all `p` candidates modulo
+/// `p^(k+1)`, which is only practical for small `p`.
+fn sqrts_mod_prime_power(a: u64, p: u64, e: u64) -> Vec<u64> {
+    let pe = p.pow(e as u32);
+    let a = a % pe;
+    if p != 2 && !a.is_multiple_of(p) {
+        let mut r = match sqrt_mod_prime(a, p) {
+            Some(r) => r,
+            None => return Vec::new(),
+        };
+        let mut pk = p;
+        for _ in 1..e {
+            pk *= p;
+            // r <- r - (r^2 - a) / 2r
+            let f = (mul_mod(r, r, pk) + pk - a % pk) % pk;
+            let inv = inv_mod(mul_mod(2, r, pk), pk).unwrap();
+            r = (r + pk - mul_mod(f, inv, pk)) % pk;
+        }
+        let mut roots = vec![r, (pe - r) % pe];
+        roots.sort_unstable();
+        roots.dedup();
+        return roots;
+    }
+    let mut roots: Vec<u64> = (0..p).filter(|&x| mul_mod(x, x, p) == a % p).collect();
+    let mut pk = p;
+    for _ in 1..e {
+        let next_pk = pk * p;
+        roots = roots
+            .iter()
+            .flat_map(|&r| (0..p).map(move |t| r + t * pk))
+            .filter(|&x| mul_mod(x, x, next_pk) == a % next_pk)
+            .collect();
+        pk = next_pk;
+    }
+    roots
+}
+
+/// Solves `x^2 + d y^2 = m` with `gcd(x, y) = 1`, given every square root of `-d` modulo `m`.
+fn cornacchia_primitive(d: u64, m: u64, roots: &[u64]) -> Option<(u64, u64)> {
+    if m == 1 {
+        return Some((1, 0));
+    }
+    for &r in roots.iter().filter(|&&r| r <= m / 2) {
+        // stop at the first remainder below sqrt(m)
+        let (mut r0, mut r1) = (m, r);
+        while r1 as u128 * r1 as u128 >= m as u128 {
+            let r2 = r0 % r1;
+            r0 = r1;
+            r1 = r2;
+        }
+        let rest = m - r1 * r1;
+        if rest.is_multiple_of(d) {
+            let y = isqrt(rest / d);
+            if y * y == rest / d {
+                return Some((r1, y));
+            }
+        }
+    }
+    None
+}
+
+/// Solves `x^2 + d y^2 = n` in non-negative integers, returning one solution if any exist.
+///
+/// Solutions with `gcd(x, y) = g` come from primitive solutions for `n / g^2`, so each square
+/// `g^2` dividing `n` is tried in turn, starting with `g = 1`.  For each, every square root of
+/// `-d` modulo `n / g^2` is assembled from roots modulo its prime power factors, found by
+/// Tonelli-Shanks and Hensel lifting, and passed to Cornacchia's algorithm.
+///
+/// Primes dividing both `d` and `n`, and the prime 2, are handled by a slower search that costs
+/// about `p` steps per power of `p`, so `d` should be small or share only small factors with
+/// `n`.  With `d = 1` this finds sums of two squares, like `two_square_decompose()`, though not
+/// necessarily the same one.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::cornacchia;
+///
+/// fn main() {
+///     // primes p ≡ 1 or 3 (mod 8) are x^2 + 2y^2
+///     assert_eq!(cornacchia(2, 1_000_000_009), Some((29_953, 7_170)));
+///     assert_eq!(cornacchia(2, 5), None);
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `d` is 0.
+pub fn cornacchia(d: u64, n: u64) -> Option<(u64, u64)> {
+    assert!(d > 0, "cornacchia called with d = 0");
+    if n == 0 {
+        return Some((0, 0));
+    }
+    let pf = factor(n);
+    // g ranges over the square roots of the square divisors of n
+    let mut gs = vec![1_u64];
+    for (p, e) in pf.iter() {
+        let p = p.get();
+        let mut next = Vec::new();
+        for &g in gs.iter() {
+            let mut pg = g;
+            for _ in 0..=e / 2 {
+                next.push(pg);
+                pg *= p;
+            }
+        }
+        gs = next;
+    }
+    gs.sort_unstable();
+    for g in gs {
+        let m = n / (g * g);
+        // all square roots of -d modulo m, combined over its prime powers
+        let mut roots = vec![0_u64];
+        let mut modulus = 1_u64;
+        for (p, e) in factor(m).iter() {
+            let (p, pe) = (p.get(), p.get().pow(e as u32));
+            let target = (pe - d % pe) % pe;
+            let local = sqrts_mod_prime_power(target, p, e);
+            roots = roots
+                .iter()
+                .flat_map(|&r| local.iter().map(move |&s| crt(r, modulus, s, pe)))
+                .collect();
+            modulus *= pe;
+        }
+        if let Some((x, y)) = cornacchia_primitive(d, m, &roots) {
+            return Some((x * g, y * g));
+        }
+    }
+    None
+}
+
+/// Writes `n` as a sum of four squares, `n = a^2 + b^2 + c^2 + d^2` with `a <= b <= c <= d`.
+///
+/// By Lagrange's four-square theorem this is always possible.  Factors of 4 are removed first
+/// and scaled back in at the end.  Small values are searched directly, and the rest use the
+/// Rabin-Shallit approach: pick `a` and `b` at random until `m - a^2 - b^2` is a prime
+/// `p ≡ 1 (mod 4)` or twice one, checked with `is_u64_prime()`, and split that as a sum of two
+/// squares.  About log(n) attempts are needed on average.
+///
+/// The random choices come from a fixed pseudorandom sequence, so the same `n` always gives the
+/// same result.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::four_square_decompose;
+///
+/// fn main() {
+///     let (a, b, c, d) = four_square_decompose(7);
+///     assert_eq!((a, b, c, d), (1, 1, 1, 2));
+///     let (a, b, c, d) = four_square_decompose(1 << 40 | 12345);
+///     assert_eq!(a * a + b * b + c * c + d * d, 1 << 40 | 12345);
+/// }
+/// ```
+pub fn four_square_decompose(n: u64) -> (u64, u64, u64, u64) {
+    if n == 0 {
+        return (0, 0, 0, 0);
+    }
+    let k = n.trailing_zeros() / 2;
+    let m = n >> (2 * k);
+    let mut res = if m < 1 << 16 {
+        small_four_squares(m)
+    } else {
+        random_four_squares(m)
+    };
+    res.sort_unstable();
+    let [a, b, c, d] = res;
+    (a << k, b << k, c << k, d << k)
+}
+
+/// Finds a four square decomposition of a small `m` by searching the first three values.
+fn small_four_squares(m: u64) -> [u64; 4] {
+    for a in (0..=isqrt(m)).rev() {
+        let ra = m - a * a;
+        for b in (0..=isqrt(ra).min(a)).rev() {
+            let rb = ra - b * b;
+            for c in (0..=isqrt(rb).min(b)).rev() {
+                let rc = rb - c * c;
+                let d = isqrt(rc);
+                if d * d == rc {
+                    return [a, b, c, d];
+                }
+            }
+        }
+    }
+    unreachable!("every integer is a sum of four squares")
+}
+
+/// Finds a four square decomposition of `m`, which isn't divisible by 4, with random choices of
+/// the first two values.
+fn random_four_squares(m: u64) -> [u64; 4] {
+    // xorshift64*, seeded from m
+    let mut state = (m ^ 0x9e37_79b9_7f4a_7c15) | 1;
+    let mut next = move |bound: u64| {
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        state.wrapping_mul(0x2545_f491_4f6c_dd1d) % (bound + 1)
+    };
+    loop {
+        let a = next(isqrt(m));
+        let ra = m - a * a;
+        let b = next(isqrt(ra));
+        let r = ra - b * b;
+        let p = if r & 1 == 0 { r / 2 } else { r };
+        if p & 3 == 1 && is_u64_prime(p) {
+            let (c, d) = two_square_decompose(r).unwrap();
+            return [a, b, c, d];
+        }
+    }
+}
+
+/// Finds the fundamental solution of Pell's equation `x^2 - d y^2 = 1`.
+///
+/// The solution is read off the continued fraction expansion of `sqrt(d)`: with period length
+/// `r`, it is the convergent just before the end of the first period when `r` is even, and of
+/// the second period when `r` is odd.  All other positive solutions are powers of it, as
+/// `x + y sqrt(d) = (x1 + y1 sqrt(d))^k`.
+///
+/// Solutions grow very irregularly with `d`, so the convergents are computed with checked u128
+/// arithmetic.  Returns `None` if `d` is a perfect square (including 0), where there is no
+/// solution with `y > 0`, or if the solution does not fit in a u128.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::solve_pell;
+///
+/// fn main() {
+///     assert_eq!(solve_pell(2), Some((3, 2)));
+///     assert_eq!(solve_pell(61), Some((1_766_319_049, 226_153_980)));
+///     assert_eq!(solve_pell(64), None);
+/// }
+/// ```
+pub fn solve_pell(d: u64) -> Option<(u128, u128)> {
+    let a0 = isqrt(d);
+    if a0 * a0 == d {
+        return None;
+    }
+    // sqrt(d) = [a0; a1, a2, ...], with the complete quotients (sqrt(d) + m) / den
+    let (mut m, mut den, mut a) = (0_u64, 1_u64, a0);
+    // convergents h/k for the previous two terms
+    let (mut h_prev, mut h) = (1_u128, a0 as u128);
+    let (mut k_prev, mut k) = (0_u128, 1_u128);
+    let mut i = 0;
+    loop {
+        m = den * a - m;
+        den = (d - m * m) / den;
+        a = (a0 + m) / den;
+        i += 1;
+        if a == 2 * a0 && i & 1 == 0 {
+            return Some((h, k));
+        }
+        let h_next = (a as u128).checked_mul(h)?.checked_add(h_prev)?;
+        let k_next = (a as u128).checked_mul(k)?.checked_add(k_prev)?;
+        h_prev = h;
+        h = h_next;
+        k_prev = k;
+        k = k_next;
+    }
+}
+
+/// Finds a Goldbach partition of `n`, two primes `p <= q` with `p + q = n`.
+///
+/// Tries the primes `p` from 2 upwards, testing `n - p` with `is_u64_prime()`, so the returned
+/// pair has the smallest possible `p`.  In every verified case up to 4 * 10^18 that `p` is below
+/// 10,000, so only a few thousand primality tests are ever needed.
+///
+/// Returns `None` if `n` is odd or less than 4.  Goldbach's conjecture, verified far beyond
+/// 2^64, says that every other `n` has a partition.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::goldbach;
+///
+/// fn main() {
+///     let (p, q) = goldbach(100).unwrap();
+///     assert_eq!((p.get(), q.get()), (3, 97));
+/// }
+/// ```
+pub fn goldbach(n: u64) -> Option<(Prime, Prime)> {
+    if n < 4 || n & 1 == 1 {
+        return None;
+    }
+    CertIter::all()
+        .take_while(|p| p.get() <= n / 2)
+        .find_map(|p| Prime::new(n - p.get()).map(|q| (p, q)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_two_squares(n: u64) {
+        match two_square_decompose(n) {
+            Some((a, b)) => {
+                assert!(a <= b);
+                assert_eq!(a as u128 * a as u128 + b as u128 * b as u128, n as u128, "{} = {}^2 + {}^2", n, a, b);
+            }
+            None => assert_eq!(r2(n), 0, "no decomposition found for {}", n),
+        }
+    }
+
+    #[test]
+    fn two_squares() {
+        for n in 0..20_000 {
+            check_two_squares(n);
+        }
+        for n in u64::MAX - 1000..=u64::MAX {
+            check_two_squares(n);
+        }
+        check_two_squares(MAX_U64_PRIME);
+        check_two_squares(1 << 63);
+        check_two_squares(4_294_967_291 * 4_294_967_291);
+        check_two_squares(5_u64.pow(27));
+    }
+
+    fn check_four_squares(n: u64) {
+        let (a, b, c, d) = four_square_decompose(n);
+        assert!(a <= b && b <= c && c <= d);
+        let sum: u128 = [a, b, c, d].iter().map(|&x| x as u128 * x as u128).sum();
+        assert_eq!(sum, n as u128, "{} = {}^2 + {}^2 + {}^2 + {}^2", n, a, b, c, d);
+    }
+
+    #[test]
+    fn four_squares() {
+        for n in 0..100_000 {
+            check_four_squares(n);
+        }
+        for n in u64::MAX - 1000..=u64::MAX {
+            check_four_squares(n);
+        }
+        for k in 0..32 {
+            check_four_squares(7 << (2 * k));
+            check_four_squares(u64::MAX >> (2 * k));
+        }
+        check_four_squares(MAX_U64_PRIME);
+        check_four_squares(0x9e37_79b9_7f4a_7c15);
+    }
+
+    #[test]
+    fn pell() {
+        use num::BigUint;
+        let mut overflows = 0;
+        for d in 0..3000_u64 {
+            let r = isqrt(d);
+            match solve_pell(d) {
+                None if r * r == d => (),
+                None => overflows += 1,
+                Some((x, y)) => {
+                    // nothing smaller, checked as far as is quick
+                    for y1 in 1..y.min(1000) {
+                        let v = d as u128 * y1 * y1 + 1;
+                        let x1 = isqrt(v as u64) as u128;
+                        assert!(x1 * x1 != v && (x1 + 1) * (x1 + 1) != v, "solve_pell({}) is not minimal", d);
+                    }
+                    let (x, y) = (BigUint::from(x), BigUint::from(y));
+                    assert_eq!(&x * &x, BigUint::from(d) * &y * &y + 1_u32, "solve_pell({})", d);
+                }
+            }
+        }
+        // the first solution that doesn't fit, x has 158 bits
+        assert_eq!(overflows, 84);
+        assert_eq!(solve_pell(1021), None);
+        assert_eq!(solve_pell(109), Some((158_070_671_986_249, 15_140_424_455_100)));
+        assert_eq!(solve_pell(u64::MAX), Some((1 << 32, 1)));
+        assert_eq!(solve_pell(u64::MAX - 1), Some((u64::MAX as u128, 1 << 32)));
+    }
+
+    #[test]
+    fn goldbach_partitions() {
+        for n in 0..20_000 {
+            match goldbach(n) {
+                None => assert!(n < 4 || n & 1 == 1),
+                Some((p, q)) => {
+                    assert_eq!(p.get() + q.get(), n);
+                    assert!(p <= q);
+                    let first = (2..=n / 2).find(|&a| is_u64_prime(a) && is_u64_prime(n - a));
+                    assert_eq!(first, Some(p.get()));
+                }
+            }
+        }
+        let (p, q) = goldbach(u64::MAX - 1).unwrap();
+        assert_eq!(p.get() + q.get(), u64::MAX - 1);
+    }
+
+    #[test]
+    fn compare_triples() {
+        use num::Integer;
+        for n in 1..=200_u64 {
+            let mut hyp = Vec::new();
+            for a in 1..n {
+                let b2 = n * n - a * a;
+                let b = isqrt(b2);
+                if b * b == b2 && a < b {
+                    hyp.push((a, b, n));
+                }
+            }
+            assert_eq!(pythagorean_triples_with_hypotenuse(n, false), hyp, "hypotenuse {}", n);
+            hyp.retain(|&(a, b, _)| a.gcd(&b) == 1);
+            assert_eq!(pythagorean_triples_with_hypotenuse(n, true), hyp, "hypotenuse {}", n);
+
+            // c - b >= 1 forces b <= (n^2 - 1) / 2
+            let mut leg = Vec::new();
+            for b in 1..=(n * n) / 2 {
+                let c2 = n * n + b * b;
+                let c = isqrt(c2);
+                if c * c == c2 {
+                    leg.push((n as u128, b as u128, c as u128));
+                }
+            }
+            assert_eq!(pythagorean_triples_with_leg(n, false), leg, "leg {}", n);
+            leg.retain(|&(a, b, _)| a.gcd(&b) == 1);
+            assert_eq!(pythagorean_triples_with_leg(n, true), leg, "leg {}", n);
+        }
+    }
+
+    #[test]
+    fn big_triples() {
+        let c = 5_u64.pow(27);
+        let triples = pythagorean_triples_with_hypotenuse(c, false);
+        assert_eq!(triples.len(), 27);
+        for &(a, b, c) in triples.iter() {
+            assert_eq!(a as u128 * a as u128 + b as u128 * b as u128, c as u128 * c as u128);
+        }
+        assert_eq!(pythagorean_triples_with_hypotenuse(c, true).len(), 1);
+        let a = u64::MAX;
+        for (a, b, c) in pythagorean_triples_with_leg(a, true) {
+            assert_eq!(c - b, (a * a) / (c + b));
+        }
+    }
+
+    #[test]
+    fn compare_cornacchia() {
+        for d in 1..=12_u64 {
+            let limit = 3000;
+            let mut solvable = vec![false; limit as usize + 1];
+            for x in 0..=isqrt(limit) {
+                for y in 0..=isqrt(limit / d) {
+                    let n = x * x + d * y * y;
+                    if n <= limit {
+                        solvable[n as usize] = true;
+                    }
+                }
+            }
+            for (n, &ok) in solvable.iter().enumerate() {
+                let n = n as u64;
+                match cornacchia(d, n) {
+                    Some((x, y)) => assert_eq!(x * x + d * y * y, n, "cornacchia({}, {})", d, n),
+                    None => assert!(!ok, "cornacchia({}, {}) found nothing", d, n),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn big_cornacchia() {
+        let cases = [(1, 3_000_000_000, 123_456_789), (3, 2_000_000_001, 1_000_000_000),
+                     (7, 1 << 31, 1 << 30), (5, 999_999_999, 1_333_333_333), (11, 0, 1_000_000_007),
+                     (2, 2_147_483_647, 2_147_483_647), (12, 1 << 31, 1 << 29)];
+        for &(d, x0, y0) in cases.iter() {
+            let n: u64 = x0 * x0 + d * y0 * y0;
+            let (x, y) = cornacchia(d, n).unwrap();
+            assert_eq!(x as u128 * x as u128 + d as u128 * y as u128 * y as u128, n as u128);
+        }
+        let (x, y) = cornacchia(1, MAX_U64_PRIME).unwrap();
+        assert_eq!(x as u128 * x as u128 + y as u128 * y as u128, MAX_U64_PRIME as u128);
+        // 5 ≡ 2 (mod 3) divides 2^64 - 1 exactly once
+        assert_eq!(cornacchia(3, u64::MAX), None);
+        assert_eq!(cornacchia(1, 3 * 1_000_000_007), None);
+    }
+
+    #[test]
+    fn small_r2() {
+        let limit = 5000_i64;
+        let mut counts = vec![0_u64; limit as usize + 1];
+        let r = (limit as f64).sqrt() as i64 + 1;
+        for a in -r..=r {
+            for b in -r..=r {
+                let n = a * a + b * b;
+                if n <= limit {
+                    counts[n as usize] += 1;
+                }
+            }
+        }
+        for (n, &c) in counts.iter().enumerate() {
+            assert_eq!(r2(n as u64), c, "r2({})", n);
+        }
+    }
+}
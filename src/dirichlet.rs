@@ -0,0 +1,253 @@
+use super::*;
+
+use num::{ One, Zero };
+use std::ops::{ Add, Div, Mul, Sub };
+
+/// Evaluates the Dirichlet convolution `(f ∗ g)(n)`, the sum of `f(d) g(n/d)` over all divisors
+/// `d` of `n`.
+///
+/// Factors `n` and enumerates its divisors, so each call costs a factorization plus d(n)
+/// evaluations of `f` and of `g`.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::dirichlet_convolution;
+///
+/// fn main() {
+///     // 1 ∗ 1 counts the divisors
+///     assert_eq!(dirichlet_convolution(|_| 1, |_| 1, 360), 24);
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `n` is 0.
+pub fn dirichlet_convolution<T, F, G>(mut f: F, mut g: G, n: u64) -> T
+    where T: Zero + Mul<Output = T>, F: FnMut(u64) -> T, G: FnMut(u64) -> T
+{
+    let mut res = T::zero();
+    factor(n).for_all_divisors(|d| {
+        let term = f(d) * g(n / d);
+        res = std::mem::replace(&mut res, T::zero()) + term;
+    });
+    res
+}
+
+/// Convolves two tabulated arithmetic functions.
+///
+/// The tables are indexed by `n`, so `f[n]` holds f(n) and `f[0]` is ignored.  The result covers
+/// every `n` present in both tables, with 0 at index 0.  The whole table is computed in
+/// O(N log N) time by running over the multiples of each index.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::dirichlet_convolve_tables;
+///
+/// fn main() {
+///     let ones = vec![1_u64; 11];
+///     let d = dirichlet_convolve_tables(&ones, &ones);
+///     assert_eq!(d, vec![0, 1, 2, 2, 3, 2, 4, 2, 4, 3, 4]);
+/// }
+/// ```
+pub fn dirichlet_convolve_tables<T>(f: &[T], g: &[T]) -> Vec<T>
+    where T: Clone + Zero + Mul<Output = T> + Add<Output = T>
+{
+    let len = f.len().min(g.len());
+    let mut res = vec![T::zero(); len];
+    for i in 1..len {
+        for j in 1..=(len - 1) / i {
+            let term = f[i].clone() * g[j].clone();
+            res[i * j] = std::mem::replace(&mut res[i * j], T::zero()) + term;
+        }
+    }
+    res
+}
+
+/// Computes the Dirichlet inverse of a tabulated arithmetic function.
+///
+/// The inverse is the function `g` with `f ∗ g = ε`, where ε(1) = 1 and ε(n) = 0 otherwise.  The
+/// table is indexed like those of `dirichlet_convolve_tables()`, and the result has the same
+/// length with 0 at index 0.  It is built in O(N log N) time from `g(1) = 1/f(1)` and
+/// `g(n) = -(1/f(1)) Σ f(n/d) g(d)` over the divisors `d < n` of `n`.
+///
+/// With integer types every division is by f(1), so it is exact when f(1) is 1 or -1, as it is
+/// for every multiplicative function.  The inverse of a multiplicative function is multiplicative
+/// too.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::dirichlet_inverse_table;
+///
+/// fn main() {
+///     // the inverse of the constant function 1 is the Möbius function
+///     let mu = dirichlet_inverse_table(&[1_i64; 11]);
+///     assert_eq!(mu, vec![0, 1, -1, -1, 0, -1, 1, -1, 0, 0, 1]);
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if the table includes f(1) and it is zero, since then there is no inverse.
+pub fn dirichlet_inverse_table<T>(f: &[T]) -> Vec<T>
+    where T: Clone + Zero + One + Sub<Output = T> + Div<Output = T>
+{
+    let len = f.len();
+    let mut res = vec![T::zero(); len];
+    if len < 2 {
+        return res;
+    }
+    assert!(!f[1].is_zero(), "dirichlet_inverse_table called with f(1) = 0");
+    // res[n] holds the partial sum of f(n/d) g(d) until n is reached
+    for d in 1..len {
+        res[d] = if d == 1 {
+            T::one() / f[1].clone()
+        } else {
+            (T::zero() - std::mem::replace(&mut res[d], T::zero())) / f[1].clone()
+        };
+        for k in 2..=(len - 1) / d {
+            let term = f[k].clone() * res[d].clone();
+            res[d * k] = std::mem::replace(&mut res[d * k], T::zero()) + term;
+        }
+    }
+    res
+}
+
+/// Computes the Dirichlet inverse of an arithmetic function over `0..=limit`.
+///
+/// Tabulates `f` on `1..=limit` and passes the table to `dirichlet_inverse_table()`.
+///
+/// # Panics
+///
+/// Panics if `limit` is at least 1 and f(1) is zero.
+pub fn dirichlet_inverse<T, F>(mut f: F, limit: usize) -> Vec<T>
+    where T: Clone + Zero + One + Sub<Output = T> + Div<Output = T>, F: FnMut(u64) -> T
+{
+    let table: Vec<T> = (0..=limit)
+        .map(|n| if n == 0 { T::zero() } else { f(n as u64) })
+        .collect();
+    dirichlet_inverse_table(&table)
+}
+
+/// Applies Möbius inversion, computing `f(n)` as the sum of `μ(n/d) g(d)` over all divisors `d`
+/// of `n`.
+///
+/// This recovers `f` from `g = f ∗ 1`, the function whose values are the sums of `f` over
+/// divisors.  Only the divisors `d` with `n/d` squarefree contribute, so `g` is called 2^ω(n)
+/// times, where ω(n) is the number of distinct prime factors of `n`.
+///
+/// The terms with positive sign are added up before those with negative sign are subtracted, so
+/// unsigned types work whenever the result itself is non-negative.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::mobius_invert;
+///
+/// fn main() {
+///     // 6 * (binary Lyndon words of length 6), from the 2^d strings with period dividing d
+///     assert_eq!(mobius_invert(|d| 1_u64 << d, 6), 6 * 9);
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `n` is 0.
+pub fn mobius_invert<T, G>(mut g: G, n: u64) -> T
+    where T: Zero + Sub<Output = T>, G: FnMut(u64) -> T
+{
+    let primes: Vec<u64> = factor(n).iter().map(|(p, _)| p.get()).collect();
+    let mut plus = T::zero();
+    let mut minus = T::zero();
+    for mask in 0_u32..(1 << primes.len()) {
+        let mut d = n;
+        for (i, &p) in primes.iter().enumerate() {
+            if mask & (1 << i) != 0 {
+                d /= p;
+            }
+        }
+        if mask.count_ones() & 1 == 0 {
+            plus = plus + g(d);
+        } else {
+            minus = minus + g(d);
+        }
+    }
+    plus - minus
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_convolutions() {
+        for n in 1..3000 {
+            assert_eq!(dirichlet_convolution(|_| 1, |_| 1, n), divisor_count(n));
+            assert_eq!(dirichlet_convolution(|d| d, |_| 1, n), divisor_sum(n));
+            assert_eq!(dirichlet_convolution(|d| mobius(d, 1), |_| 1, n), (n == 1) as i64);
+            assert_eq!(dirichlet_convolution(|d| mobius(d, 1) * d as i64, |_| 1_i64, n),
+                       factor(n).iter().map(|(p, _)| 1 - p.get() as i64).product::<i64>());
+            assert_eq!(dirichlet_convolution(euler_totient, |_| 1, n), n);
+        }
+    }
+
+    #[test]
+    fn inversions() {
+        for n in 1..3000 {
+            assert_eq!(mobius_invert(|d| d, n), euler_totient(n));
+            assert_eq!(mobius_invert(divisor_sum, n), n);
+            assert_eq!(mobius_invert(divisor_count, n), 1);
+            assert_eq!(mobius_invert(|_| 1_i64, n), (n == 1) as i64);
+        }
+        // OEIS A001037, binary Lyndon words
+        let lyndon: Vec<u64> = (1..=12).map(|n| mobius_invert(|d| 1_u64 << d, n) / n).collect();
+        assert_eq!(lyndon, vec![2, 1, 2, 3, 6, 9, 18, 30, 56, 99, 186, 335]);
+    }
+
+    #[test]
+    fn inverses() {
+        let limit = 3000;
+        let mu = dirichlet_inverse(|_| 1_i64, limit);
+        let mu_id = dirichlet_inverse(|n| n as i64, limit);
+        let one = dirichlet_inverse(|n| mobius(n, 1), limit);
+        for n in 1..=limit {
+            let m = mobius(n as u64, 1);
+            assert_eq!(mu[n], m);
+            assert_eq!(mu_id[n], m * n as i64);
+            assert_eq!(one[n], 1);
+        }
+        // a function that isn't multiplicative, with f(1) = -1
+        let f: Vec<i64> = (0..=limit as i64).map(|n| if n == 1 { -1 } else { n * n % 7 - 3 }).collect();
+        let g = dirichlet_inverse_table(&f);
+        let eps = dirichlet_convolve_tables(&f, &g);
+        for (n, &e) in eps.iter().enumerate().skip(1) {
+            assert_eq!(e, (n == 1) as i64, "(f ∗ f^-1)({})", n);
+        }
+        // f(1) = 2 with floating point values
+        let f: Vec<f64> = (0..=100).map(|n| if n == 1 { 2.0 } else { n as f64 }).collect();
+        let eps = dirichlet_convolve_tables(&f, &dirichlet_inverse_table(&f));
+        for (n, &e) in eps.iter().enumerate().skip(1) {
+            assert!((e - (n == 1) as i64 as f64).abs() < 1e-9);
+        }
+        assert_eq!(dirichlet_inverse(|_| 1_i64, 0), vec![0]);
+    }
+
+    #[test]
+    fn compare_tables() {
+        let limit = 3000;
+        let id: Vec<i64> = (0..=limit).collect();
+        let mu: Vec<i64> = (0..=limit).map(|n| if n == 0 { 0 } else { mobius(n as u64, 1) }).collect();
+        let phi = dirichlet_convolve_tables(&id, &mu);
+        assert_eq!(phi.len(), limit as usize + 1);
+        assert_eq!(phi[0], 0);
+        for n in 1..=limit {
+            assert_eq!(phi[n as usize], euler_totient(n as u64) as i64);
+            let direct = dirichlet_convolution(|d| id[d as usize], |d| mu[d as usize], n as u64);
+            assert_eq!(phi[n as usize], direct);
+        }
+        assert_eq!(dirichlet_convolve_tables(&id[..10], &mu).len(), 10);
+        assert!(dirichlet_convolve_tables::<i64>(&[], &[]).is_empty());
+    }
+}
@@ -4,25 +4,99 @@
 
 use super::*;
 
-use std::collections::BTreeMap;
+use smallvec::SmallVec;
+use std::cmp::Reverse;
+use std::collections::{ BTreeMap, BinaryHeap };
+use std::convert::TryFrom;
+use std::iter::FusedIterator;
+use std::ops::{ Index, Mul, MulAssign };
 
 
-#[derive(Eq, Ord, PartialEq, PartialOrd, Clone, Debug)]
+#[derive(Eq, Ord, PartialEq, PartialOrd, Clone, Debug, Default, Hash)]
 /// Represents a collection of powers of prime factors.
+///
+/// The form is canonical: primes are kept in increasing order and zero exponents are never
+/// stored, so two factorizations of the same number are always equal and hash the same.  This
+/// makes them usable as `HashMap` and `HashSet` keys.
+///
+/// The factors are stored inline in a sorted array with room for 15 primes, which is as many
+/// distinct primes as a u64 can have, so factoring a u64 never allocates.  Larger products built
+/// with `add_pf()`, `lcm()` and the like spill over to the heap.
 pub struct PrimeFactorization {
-    facs: BTreeMap<Prime, u64>,
+    facs: SmallVec<[(Prime, u64); 15]>,
 }
 
 impl PrimeFactorization {
     /// Creates a new PrimeFactoriazation
     pub fn new() -> Self {
-        PrimeFactorization { facs: BTreeMap::new() }
+        PrimeFactorization { facs: SmallVec::new() }
+    }
+
+    /// Finds where `p` is, or would go, in `facs`.
+    fn find(&self, p: Prime) -> Result<usize, usize> {
+        self.facs.binary_search_by_key(&p, |&(q, _)| q)
     }
+
     /// Add a power of a prime to this factorization.
     pub fn add(&mut self, prime: Prime, power: u64) {
         if power > 0 {
-            *self.facs.entry(prime).or_insert(0) += power;
+            match self.find(prime) {
+                Ok(i) => self.facs[i].1 += power,
+                Err(i) => self.facs.insert(i, (prime, power)),
+            }
+        }
+    }
+
+    /// Remove a power of a prime from this factorization, dividing the represented number by
+    /// `prime^power`.
+    ///
+    /// Fails without changing anything if the exponent of `prime` is less than `power`.
+    pub fn remove(&mut self, prime: Prime, power: u64) -> Result<(), FactorUnderflowError> {
+        let available = self.valuation(prime);
+        if available < power {
+            return Err(FactorUnderflowError { prime, available, requested: power });
+        }
+        if let Ok(i) = self.find(prime) {
+            if available == power {
+                self.facs.remove(i);
+            } else {
+                self.facs[i].1 -= power;
+            }
         }
+        Ok(())
+    }
+
+    /// Remove all the factors in the other PrimeFactorization from this one, dividing the
+    /// represented number by the one `other` represents.
+    ///
+    /// Fails without changing anything if `other` doesn't divide this.  The error describes the
+    /// smallest prime that has too large an exponent in `other`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::factor;
+    ///
+    /// fn main() {
+    ///     let mut pf = factor(360);
+    ///     pf.sub_pf(&factor(12)).unwrap();
+    ///     assert_eq!(pf, factor(30));
+    ///     let err = pf.sub_pf(&factor(4)).unwrap_err();
+    ///     assert_eq!((err.prime.get(), err.available, err.requested), (2, 1, 2));
+    ///     assert_eq!(pf, factor(30));
+    /// }
+    /// ```
+    pub fn sub_pf(&mut self, other: &Self) -> Result<(), FactorUnderflowError> {
+        for (prime, requested) in other.iter() {
+            let available = self.valuation(prime);
+            if available < requested {
+                return Err(FactorUnderflowError { prime, available, requested });
+            }
+        }
+        for (p, pow) in other.iter() {
+            self.remove(p, pow).unwrap();
+        }
+        Ok(())
     }
 
     /// Add all the factors in the other PrimeFactorization into this one.
@@ -34,17 +108,66 @@ impl PrimeFactorization {
     /// Create an iterator over the contained factors and powers.
     pub fn iter<'a>(&'a self) -> impl 'a + Iterator<Item = (Prime, u64)>
     {
-        self.facs.iter().map(|(x,y)| (*x, *y))
+        self.facs.iter().copied()
     }
     /// Multiply out the contained factors and powers, yielding the product they represent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the product doesn't fit in a u64, which can happen after `add_pf()`, `pow()` or
+    /// `lcm()`.  See `checked_product()`.
     pub fn product(&self) -> u64 {
-        let mut res = 1;
+        self.checked_product().expect("PrimeFactorization::product overflowed a u64")
+    }
+
+    /// Multiply out the contained factors and powers, or return `None` if the product doesn't fit
+    /// in a u64.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::factor;
+    ///
+    /// fn main() {
+    ///     assert_eq!(factor(1 << 32).checked_product(), Some(1 << 32));
+    ///     assert_eq!(factor(1 << 32).pow(2).checked_product(), None);
+    /// }
+    /// ```
+    pub fn checked_product(&self) -> Option<u64> {
+        let mut res: u64 = 1;
         for (p, pow) in self.iter() {
-            for _ in 0..pow {
-                res *= p.get();
-            }
+            // p >= 2, so an exponent beyond u32 overflows anyway
+            let pp = p.get().checked_pow(u32::try_from(pow).ok()?)?;
+            res = res.checked_mul(pp)?;
         }
-        res
+        Some(res)
+    }
+
+    /// Multiply out the contained factors and powers in u128 arithmetic, or return `None` if the
+    /// product doesn't fit in a u128.
+    ///
+    /// This evaluates factorizations built up beyond the u64 range, like products of factored u64s
+    /// or `factor_factorial()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::{ factor, factor_factorial };
+    ///
+    /// fn main() {
+    ///     let pf = factor(u64::MAX) * factor(u64::MAX);
+    ///     assert_eq!(pf.product_u128(), Some(u64::MAX as u128 * u64::MAX as u128));
+    ///     assert_eq!(factor_factorial(34).product_u128(), Some((1..=34).product()));
+    ///     assert_eq!(factor_factorial(35).product_u128(), None);
+    /// }
+    /// ```
+    pub fn product_u128(&self) -> Option<u128> {
+        let mut res: u128 = 1;
+        for (p, pow) in self.iter() {
+            let pp = u128::from(p.get()).checked_pow(u32::try_from(pow).ok()?)?;
+            res = res.checked_mul(pp)?;
+        }
+        Some(res)
     }
 
     /// Calculates Euler's totient function.
@@ -60,6 +183,151 @@ impl PrimeFactorization {
         res
     }
 
+    /// Calculates Jordan's totient function J_k, using u128 arithmetic.
+    ///
+    /// Returns `None` if the result does not fit in a u128.
+    pub fn jordan_totient(&self, k: u32) -> Option<u128> {
+        let mut res: u128 = 1;
+        for (p, pow) in self.iter() {
+            let pk = (p.get() as u128).checked_pow(k)?;
+            res = res.checked_mul(pk - 1)?;
+            for _ in 1..pow {
+                res = res.checked_mul(pk)?;
+            }
+        }
+        Some(res)
+    }
+
+    /// Calculates the number of divisors, d(n), without enumerating them.
+    ///
+    /// This is the product of `e + 1` over the exponents `e`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result does not fit in a u64, which only happens for factorizations built
+    /// up far beyond the u64 range.  Use `checked_divisor_count()` to avoid this.
+    pub fn divisor_count(&self) -> u64 {
+        self.checked_divisor_count().expect("divisor_count overflowed u64")
+    }
+
+    /// Calculates the number of divisors, d(n), or `None` on overflow.
+    pub fn checked_divisor_count(&self) -> Option<u64> {
+        self.iter().try_fold(1_u64, |res, (_, pow)| res.checked_mul(pow.checked_add(1)?))
+    }
+
+    /// Calculates σ_k(n), the sum of the k-th powers of the divisors.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result does not fit in a u64.  Use `checked_sigma_k()` to avoid this.
+    pub fn sigma_k(&self, k: u32) -> u64 {
+        self.checked_sigma_k(k).expect("sigma_k overflowed u64")
+    }
+
+    /// Calculates σ_k(n), the sum of the k-th powers of the divisors, or `None` on overflow.
+    pub fn checked_sigma_k(&self, k: u32) -> Option<u64> {
+        u64::try_from(self.sigma_k_u128(k)?).ok()
+    }
+
+    /// Calculates σ_k(n), the sum of the k-th powers of the divisors, using u128 arithmetic.
+    ///
+    /// Each prime power contributes the geometric series `1 + p^k + ... + p^(k e)`, which is
+    /// evaluated in closed form as `(p^(k (e+1)) - 1) / (p^k - 1)`.  Returns `None` if the
+    /// result does not fit in a u128.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::factor;
+    ///
+    /// fn main() {
+    ///     assert_eq!(factor(12).sigma_k_u128(2), Some(1 + 4 + 9 + 16 + 36 + 144));
+    ///     assert_eq!(factor(1 << 63).sigma_k_u128(2), Some(u128::MAX / 3));
+    ///     assert_eq!(factor(u64::MAX).sigma_k_u128(3), None);
+    /// }
+    /// ```
+    pub fn sigma_k_u128(&self, k: u32) -> Option<u128> {
+        let mut res: u128 = 1;
+        for (p, pow) in self.iter() {
+            res = res.checked_mul(geometric_sum(u128::from(p.get()).checked_pow(k)?, pow)?)?;
+        }
+        Some(res)
+    }
+
+    /// Calculates σ(n), the sum of the divisors.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result does not fit in a u64.  Use `checked_divisor_sum()` to avoid this.
+    pub fn divisor_sum(&self) -> u64 {
+        self.sigma_k(1)
+    }
+
+    /// Calculates σ(n), the sum of the divisors, or `None` on overflow.
+    pub fn checked_divisor_sum(&self) -> Option<u64> {
+        self.checked_sigma_k(1)
+    }
+
+    /// Calculates σ(n), the sum of the divisors, using u128 arithmetic.  Returns `None` if the
+    /// result does not fit in a u128.
+    pub fn divisor_sum_u128(&self) -> Option<u128> {
+        self.sigma_k_u128(1)
+    }
+
+    /// Calculates σ*(n), the sum of the unitary divisors, which is the product of `1 + p^e` over
+    /// the prime powers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result does not fit in a u64.  Use `checked_unitary_divisor_sum()` to avoid
+    /// this.
+    pub fn unitary_divisor_sum(&self) -> u64 {
+        self.checked_unitary_divisor_sum().expect("unitary_divisor_sum overflowed u64")
+    }
+
+    /// Calculates σ*(n), the sum of the unitary divisors, or `None` on overflow.
+    pub fn checked_unitary_divisor_sum(&self) -> Option<u64> {
+        let mut res: u64 = 1;
+        for (p, pow) in self.iter() {
+            let pp = p.get().checked_pow(u32::try_from(pow).ok()?)?;
+            res = res.checked_mul(pp.checked_add(1)?)?;
+        }
+        Some(res)
+    }
+
+    /// Calculates the unitary totient φ*(n), the product of `p^e - 1` over the prime powers.
+    ///
+    /// This counts the `k` in `1..=n` that aren't divisible by any unitary divisor of n other than
+    /// 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if n doesn't fit in a u64.
+    pub fn unitary_totient(&self) -> u64 {
+        assert!(self.checked_product().is_some(), "factorization doesn't fit in a u64");
+        self.iter().map(|(p, pow)| p.get().pow(pow as u32) - 1).product()
+    }
+
+    /// Calculates Carmichael's function λ, the exponent of the multiplicative group mod n.
+    pub fn carmichael_lambda(&self) -> u64 {
+        use num::Integer;
+        let mut res = 1;
+        for (p, pow) in self.iter() {
+            let p = p.get();
+            let lambda = if p == 2 && pow >= 3 {
+                1 << (pow - 2)
+            } else {
+                let mut l = p - 1;
+                for _ in 1..pow {
+                    l *= p;
+                }
+                l
+            };
+            res = res.lcm(&lambda);
+        }
+        res
+    }
+
     /// Calculates the Möbius function for this prime factorization.
     pub fn mobius(&self) -> i64 {
         let mut res = 1;
@@ -73,12 +341,204 @@ impl PrimeFactorization {
         res
     }
 
+    /// Returns the exponent of `p` in this factorization, which is 0 if `p` isn't present.
+    pub fn valuation(&self, p: Prime) -> u64 {
+        self.find(p).map_or(0, |i| self.facs[i].1)
+    }
+
+    /// Returns the exponent of `p` in this factorization, which is 0 if `p` isn't present.  The
+    /// same as `valuation()`, and as indexing with `pf[p]`.
+    pub fn exponent_of(&self, p: Prime) -> u64 {
+        self.valuation(p)
+    }
+
+    /// Determines whether `p` divides the represented number.
+    pub fn contains(&self, p: Prime) -> bool {
+        self.find(p).is_ok()
+    }
+
+    /// Determines whether the number this represents divides the one `other` represents, which is
+    /// when no prime has a larger exponent here than in `other`.
+    pub fn divides(&self, other: &Self) -> bool {
+        self.iter().all(|(p, pow)| pow <= other.valuation(p))
+    }
+
+    /// Divides by the number `other` represents, subtracting its exponents from these.
+    ///
+    /// Returns `None` if `other` doesn't divide this.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::factor;
+    ///
+    /// fn main() {
+    ///     assert_eq!(factor(360).try_div(&factor(12)), Some(factor(30)));
+    ///     assert_eq!(factor(360).try_div(&factor(16)), None);
+    /// }
+    /// ```
+    pub fn try_div(&self, other: &Self) -> Option<Self> {
+        let mut res = self.clone();
+        res.sub_pf(other).ok()?;
+        Some(res)
+    }
+
+    /// Calculates the greatest common divisor, taking the smaller exponent of each prime.
+    pub fn gcd(&self, other: &Self) -> Self {
+        self.iter()
+            .map(|(p, pow)| (p, pow.min(other.valuation(p))))
+            .collect()
+    }
+
+    /// Calculates the least common multiple, taking the larger exponent of each prime.
+    ///
+    /// Unlike with `num::Integer::lcm`, the result can represent numbers beyond the u64 range.
+    pub fn lcm(&self, other: &Self) -> Self {
+        let mut res = self.clone();
+        for (p, pow) in other.iter() {
+            res.add(p, pow.saturating_sub(self.valuation(p)));
+        }
+        res
+    }
+
+    /// Raises the represented number to the `k`-th power, by multiplying every exponent by `k`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an exponent overflows a u64.  See `checked_pow()`.
+    pub fn pow(&self, k: u64) -> Self {
+        self.checked_pow(k).expect("PrimeFactorization::pow overflowed an exponent")
+    }
+
+    /// Raises the represented number to the `k`-th power, or returns `None` if an exponent would
+    /// overflow a u64.
+    pub fn checked_pow(&self, k: u64) -> Option<Self> {
+        let mut res = PrimeFactorization::new();
+        for (p, pow) in self.iter() {
+            res.add(p, pow.checked_mul(k)?);
+        }
+        Some(res)
+    }
+
+    /// Takes the exact `k`-th root of the represented number, by dividing every exponent by `k`.
+    ///
+    /// Returns `None` if some exponent isn't divisible by `k`, so the number isn't a `k`-th
+    /// power.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::factor;
+    ///
+    /// fn main() {
+    ///     assert_eq!(factor(1_000_000).nth_root(3), Some(factor(100)));
+    ///     assert_eq!(factor(72).nth_root(2), None);
+    ///     assert_eq!(factor(6).pow(40).nth_root(8), Some(factor(7776)));
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is 0.
+    pub fn nth_root(&self, k: u64) -> Option<Self> {
+        assert!(k > 0, "PrimeFactorization::nth_root called with k = 0");
+        let mut res = PrimeFactorization::new();
+        for (p, pow) in self.iter() {
+            if !pow.is_multiple_of(k) {
+                return None;
+            }
+            res.add(p, pow / k);
+        }
+        Some(res)
+    }
+
+    /// Determines whether the represented number is a perfect square.
+    pub fn is_square(&self) -> bool {
+        self.is_kth_power(2)
+    }
+
+    /// Determines whether the represented number is a perfect `k`-th power, which is when every
+    /// exponent is divisible by `k`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is 0.
+    pub fn is_kth_power(&self, k: u64) -> bool {
+        assert!(k > 0, "PrimeFactorization::is_kth_power called with k = 0");
+        self.iter().all(|(_, pow)| pow.is_multiple_of(k))
+    }
+
+    /// Calculates the number of abelian groups of this order, up to isomorphism.
+    ///
+    /// This is the product of the partition numbers of the exponents.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result does not fit in a u64, which can only happen for factorizations built
+    /// up beyond the u64 range.  Use `checked_abelian_group_count()` to avoid this.
+    pub fn abelian_group_count(&self) -> u64 {
+        self.checked_abelian_group_count().expect("abelian_group_count overflowed u64")
+    }
+
+    /// Calculates the number of abelian groups of this order, or `None` on overflow.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::{ factor, factor_factorial };
+    ///
+    /// fn main() {
+    ///     assert_eq!(factor(1 << 63).checked_abelian_group_count(), Some(1_505_499));
+    ///     assert_eq!(factor_factorial(1000).checked_abelian_group_count(), None);
+    /// }
+    /// ```
+    pub fn checked_abelian_group_count(&self) -> Option<u64> {
+        self.iter().try_fold(1_u64, |res, (_, pow)| res.checked_mul(partition_count(pow)?))
+    }
+
+    /// Determines whether no prime appears to the `k`-th power or higher.
+    pub fn is_k_free(&self, k: u32) -> bool {
+        self.iter().all(|(_, pow)| pow < k as u64)
+    }
+
+    /// Calculates the radical, the product of the distinct primes in this factorization.
+    pub fn radical(&self) -> u64 {
+        self.iter().map(|(p, _)| p.get()).product()
+    }
+
+    /// Splits the represented number `n` into `(s, f)` with `n = s * f^2` and `s` squarefree.
+    pub fn squarefree_decompose(&self) -> (u64, u64) {
+        let mut s = 1;
+        let mut f = 1;
+        for (p, pow) in self.iter() {
+            let p = p.get();
+            if pow & 1 == 1 {
+                s *= p;
+            }
+            for _ in 0..pow / 2 {
+                f *= p;
+            }
+        }
+        (s, f)
+    }
+
+    /// Calculates the Liouville function, (-1)^Ω(n), where Ω(n) counts prime factors with
+    /// multiplicity.
+    pub fn liouville(&self) -> i64 {
+        let omega: u64 = self.iter().map(|(_, pow)| pow).sum();
+        if omega & 1 == 0 {
+            1
+        } else {
+            -1
+        }
+    }
+
     /// Runs a closure on all divisors of n, including 1 and n.
     ///
     /// No particular order of divisors is guaranteed.
     pub fn for_all_divisors<F: FnMut(u64)>(&self, mut f: F) {
         fn iter<F: FnMut(u64)>(n: u64, facs: &[(Prime, u64)], f: &mut F) {
-            if facs.len() == 0 {
+            if facs.is_empty() {
                 f(n)
             } else {
                 let (p,pow) = facs[0];
@@ -92,11 +552,565 @@ impl PrimeFactorization {
                 }
             }
         }
-        let facs: Vec<(Prime, u64)> = self.iter().collect();
-        iter(1, &facs, &mut f);
+        iter(1, &self.facs, &mut f);
+    }
+
+    /// Returns an iterator over all divisors of n, including 1 and n.
+    ///
+    /// Unlike `for_all_divisors()`, this can be combined with other iterator adapters and stopped
+    /// early.  The divisors come out in mixed-radix order, with the exponent of the smallest prime
+    /// varying fastest, so they are not sorted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::factor;
+    ///
+    /// fn main() {
+    ///     let divs: Vec<u64> = factor(12).divisors().collect();
+    ///     assert_eq!(divs, vec![1, 2, 4, 3, 6, 12]);
+    ///     assert_eq!(factor(720720).divisors().find(|&d| d > 1000), Some(1008));
+    /// }
+    /// ```
+    pub fn divisors(&self) -> Divisors<'_> {
+        Divisors {
+            facs: &self.facs,
+            exps: self.facs.iter().map(|_| 0).collect(),
+            powers: self.facs.iter().map(|_| 1).collect(),
+            cur: Some(1),
+        }
+    }
+
+    /// Returns an iterator over all divisors of n in increasing order, including 1 and n.
+    ///
+    /// The divisors are produced lazily, so finding the smallest divisor above some bound only
+    /// does work for the divisors up to it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::factor;
+    ///
+    /// fn main() {
+    ///     let divs: Vec<u64> = factor(12).divisors_sorted().collect();
+    ///     assert_eq!(divs, vec![1, 2, 3, 4, 6, 12]);
+    ///     let n = 614_889_782_588_491_410;
+    ///     assert_eq!(factor(n).divisors_sorted().find(|&d| d > 1_000_000_000), Some(1_001_449_141));
+    /// }
+    /// ```
+    pub fn divisors_sorted(&self) -> SortedDivisors<'_> {
+        let mut heap = BinaryHeap::new();
+        heap.push(Reverse((1, 0, 0)));
+        SortedDivisors { facs: &self.facs, heap }
+    }
+
+    /// Returns an iterator over the ways of writing n as a product of two factors, as pairs
+    /// `(d, n / d)` with `d <= n / d`, in increasing order of `d`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::factor;
+    ///
+    /// fn main() {
+    ///     let pairs: Vec<(u64, u64)> = factor(36).divisor_pairs().collect();
+    ///     assert_eq!(pairs, vec![(1, 36), (2, 18), (3, 12), (4, 9), (6, 6)]);
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if n doesn't fit in a u64.
+    pub fn divisor_pairs(&self) -> DivisorPairs<'_> {
+        DivisorPairs { n: self.product(), divs: self.divisors_sorted() }
+    }
+
+    /// Returns an iterator over the divisors of n as factorizations, in the same order as
+    /// `divisors()`.
+    ///
+    /// This saves factoring the divisors again when they are needed in factored form, and works
+    /// even when n is too large for a u64.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::factor;
+    ///
+    /// fn main() {
+    ///     let mobius: Vec<i64> = factor(12).divisor_factorizations().map(|d| d.mobius()).collect();
+    ///     assert_eq!(mobius, vec![1, -1, 0, -1, 1, 0]);
+    /// }
+    /// ```
+    pub fn divisor_factorizations(&self) -> DivisorFactorizations<'_> {
+        DivisorFactorizations {
+            facs: &self.facs,
+            exps: self.facs.iter().map(|_| 0).collect(),
+            done: false,
+        }
+    }
+
+    /// Returns an iterator over the unitary divisors of n, the divisors `d` with
+    /// `gcd(d, n / d) = 1`.
+    ///
+    /// Each is a product of some subset of the prime powers `p^e` in the factorization, so there
+    /// are `2^ω(n)` of them.  They come out in the same mixed-radix order as `divisors()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::factor;
+    ///
+    /// fn main() {
+    ///     let divs: Vec<u64> = factor(72).unitary_divisors().collect();
+    ///     assert_eq!(divs, vec![1, 8, 9, 72]);
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if n doesn't fit in a u64.
+    pub fn unitary_divisors(&self) -> UnitaryDivisors {
+        assert!(self.checked_product().is_some(), "factorization doesn't fit in a u64");
+        UnitaryDivisors {
+            prime_powers: self.iter().map(|(p, pow)| p.get().pow(pow as u32)).collect(),
+            used: self.facs.iter().map(|_| false).collect(),
+            cur: Some(1),
+        }
+    }
+}
+
+/// Iterates over the divisors of a factorization as factorizations.  See
+/// `PrimeFactorization::divisor_factorizations()`.
+#[derive(Clone, Debug)]
+pub struct DivisorFactorizations<'a> {
+    facs: &'a [(Prime, u64)],
+    /// The exponents of the next divisor.
+    exps: SmallVec<[u64; 15]>,
+    done: bool,
+}
+
+impl<'a> Iterator for DivisorFactorizations<'a> {
+    type Item = PrimeFactorization;
+    fn next(&mut self) -> Option<PrimeFactorization> {
+        if self.done {
+            return None;
+        }
+        let res = PrimeFactorization {
+            facs: self.facs.iter().zip(self.exps.iter())
+                .filter(|(_, &e)| e > 0)
+                .map(|(&(p, _), &e)| (p, e))
+                .collect(),
+        };
+        self.done = true;
+        for (e, &(_, pow)) in self.exps.iter_mut().zip(self.facs.iter()) {
+            if *e < pow {
+                *e += 1;
+                self.done = false;
+                break;
+            }
+            *e = 0;
+        }
+        Some(res)
+    }
+}
+
+impl<'a> FusedIterator for DivisorFactorizations<'a> {}
+
+/// Iterates over the unitary divisors of a factorization.  See
+/// `PrimeFactorization::unitary_divisors()`.
+#[derive(Clone, Debug)]
+pub struct UnitaryDivisors {
+    prime_powers: SmallVec<[u64; 15]>,
+    /// Which prime powers are in the current divisor, counting in binary.
+    used: SmallVec<[bool; 15]>,
+    /// The next divisor, or `None` when all have been produced.
+    cur: Option<u64>,
+}
+
+impl Iterator for UnitaryDivisors {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        let res = self.cur?;
+        self.cur = None;
+        let mut d = res;
+        for (used, &pp) in self.used.iter_mut().zip(self.prime_powers.iter()) {
+            if !*used {
+                *used = true;
+                self.cur = Some(d * pp);
+                break;
+            }
+            d /= pp;
+            *used = false;
+        }
+        Some(res)
+    }
+}
+
+impl FusedIterator for UnitaryDivisors {}
+
+/// Iterates over complementary pairs of divisors.  See `PrimeFactorization::divisor_pairs()`.
+#[derive(Clone, Debug)]
+pub struct DivisorPairs<'a> {
+    n: u64,
+    divs: SortedDivisors<'a>,
+}
+
+impl<'a> Iterator for DivisorPairs<'a> {
+    type Item = (u64, u64);
+    fn next(&mut self) -> Option<(u64, u64)> {
+        let d = self.divs.next()?;
+        if d > self.n / d {
+            // past the square root, so every remaining pair has already been produced
+            self.divs.heap.clear();
+            return None;
+        }
+        Some((d, self.n / d))
+    }
+}
+
+impl<'a> FusedIterator for DivisorPairs<'a> {}
+
+/// Iterates over the divisors of a factorization in increasing order.  See
+/// `PrimeFactorization::divisors_sorted()`.
+///
+/// The divisors form a tree, where the parent of each divisor is found by dividing out its
+/// largest prime factor once.  The children of a divisor are increasing, so a min-heap holding
+/// each produced divisor's first child and next sibling yields them all in order, with the heap
+/// growing by at most one entry per divisor.
+///
+/// # Panics
+///
+/// Panics if a divisor doesn't fit in a u64.
+#[derive(Clone, Debug)]
+pub struct SortedDivisors<'a> {
+    facs: &'a [(Prime, u64)],
+    /// Entries of (divisor, index of its largest prime, exponent of that prime).  The root 1 has
+    /// exponent 0.
+    heap: BinaryHeap<Reverse<(u64, usize, u64)>>,
+}
+
+impl<'a> Iterator for SortedDivisors<'a> {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        let Reverse((d, i, e)) = self.heap.pop()?;
+        let mul = |d: u64, p: Prime| d.checked_mul(p.get()).expect("divisor overflowed a u64");
+        // first child, raising the largest prime or else bringing in the next one
+        if let Some(&(p, pow)) = self.facs.get(i) {
+            if e < pow {
+                self.heap.push(Reverse((mul(d, p), i, e + 1)));
+            } else if let Some(&(q, _)) = self.facs.get(i + 1) {
+                self.heap.push(Reverse((mul(d, q), i + 1, 1)));
+            }
+        }
+        // next sibling, replacing one factor of the largest prime with the following prime
+        if e > 0 {
+            if let Some(&(q, _)) = self.facs.get(i + 1) {
+                self.heap.push(Reverse((mul(d / self.facs[i].0.get(), q), i + 1, 1)));
+            }
+        }
+        Some(d)
+    }
+}
+
+impl<'a> FusedIterator for SortedDivisors<'a> {}
+
+/// Iterates over the divisors of a factorization.  See `PrimeFactorization::divisors()`.
+///
+/// Works as a mixed-radix counter over the exponents, keeping the current prime powers so that
+/// each step takes only a few multiplications and divisions.
+///
+/// # Panics
+///
+/// Panics if a divisor doesn't fit in a u64.
+#[derive(Clone, Debug)]
+pub struct Divisors<'a> {
+    facs: &'a [(Prime, u64)],
+    /// The current exponent of each prime.
+    exps: SmallVec<[u64; 15]>,
+    /// Each prime raised to its current exponent.
+    powers: SmallVec<[u64; 15]>,
+    /// The next divisor, or `None` when all have been produced.
+    cur: Option<u64>,
+}
+
+impl<'a> Iterator for Divisors<'a> {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        let res = self.cur?;
+        self.cur = None;
+        let mut d = res;
+        for (i, &(p, pow)) in self.facs.iter().enumerate() {
+            if self.exps[i] < pow {
+                let p = p.get();
+                self.cur = Some(d.checked_mul(p).expect("divisor overflowed a u64"));
+                self.exps[i] += 1;
+                self.powers[i] *= p;
+                break;
+            }
+            // this digit wraps around to 0, carry into the next
+            d /= self.powers[i];
+            self.exps[i] = 0;
+            self.powers[i] = 1;
+        }
+        Some(res)
+    }
+}
+
+impl<'a> FusedIterator for Divisors<'a> {}
+
+/// Collects prime powers into a factorization, adding up the exponents of repeated primes.
+///
+/// ```
+/// use red_primality::{ factor, Prime, PrimeFactorization };
+///
+/// fn main() {
+///     let two = Prime::new(2).unwrap();
+///     let three = Prime::new(3).unwrap();
+///     let pf: PrimeFactorization = vec![(two, 1), (three, 2), (two, 2)].into_iter().collect();
+///     assert_eq!(pf, factor(72));
+/// }
+/// ```
+impl std::iter::FromIterator<(Prime, u64)> for PrimeFactorization {
+    fn from_iter<I: IntoIterator<Item = (Prime, u64)>>(iter: I) -> Self {
+        let mut pf = PrimeFactorization::new();
+        pf.extend(iter);
+        pf
+    }
+}
+
+/// Adds prime powers to a factorization, the same as calling `add()` for each one.
+impl Extend<(Prime, u64)> for PrimeFactorization {
+    fn extend<I: IntoIterator<Item = (Prime, u64)>>(&mut self, iter: I) {
+        for (p, pow) in iter {
+            self.add(p, pow);
+        }
+    }
+}
+
+/// Looks up the exponent of a prime, which is 0 if it isn't present.
+///
+/// ```
+/// use red_primality::{ factor, Prime };
+///
+/// fn main() {
+///     let pf = factor(360);
+///     assert_eq!(pf[Prime::new(2).unwrap()], 3);
+///     assert_eq!(pf[Prime::new(7).unwrap()], 0);
+/// }
+/// ```
+impl Index<Prime> for PrimeFactorization {
+    type Output = u64;
+    fn index(&self, p: Prime) -> &u64 {
+        match self.find(p) {
+            Ok(i) => &self.facs[i].1,
+            Err(_) => &0,
+        }
+    }
+}
+
+/// Multiplies the represented numbers by adding exponents, like `add_pf(rhs, 1)`.
+///
+/// ```
+/// use red_primality::factor;
+///
+/// fn main() {
+///     assert_eq!(factor(12) * factor(18), factor(216));
+///     assert_eq!(&factor(1) * &factor(35), factor(35));
+/// }
+/// ```
+impl Mul for PrimeFactorization {
+    type Output = PrimeFactorization;
+    fn mul(mut self, rhs: PrimeFactorization) -> PrimeFactorization {
+        self *= &rhs;
+        self
+    }
+}
+
+impl<'a> Mul<&'a PrimeFactorization> for &'a PrimeFactorization {
+    type Output = PrimeFactorization;
+    fn mul(self, rhs: &'a PrimeFactorization) -> PrimeFactorization {
+        let mut res = self.clone();
+        res *= rhs;
+        res
+    }
+}
+
+impl MulAssign for PrimeFactorization {
+    fn mul_assign(&mut self, rhs: PrimeFactorization) {
+        *self *= &rhs;
+    }
+}
+
+impl<'a> MulAssign<&'a PrimeFactorization> for PrimeFactorization {
+    fn mul_assign(&mut self, rhs: &'a PrimeFactorization) {
+        self.add_pf(rhs, 1);
+    }
+}
+
+/// Writes the factorization as a product of prime powers in increasing order, like `2^3 · 5^2 · 11`.
+///
+/// The alternate form `{:#}` leaves out the spaces and uses `*`, giving `2^3*5^2*11`.  The empty
+/// factorization is written as `1`.
+///
+/// ```
+/// use red_primality::factor;
+///
+/// fn main() {
+///     assert_eq!(factor(2200).to_string(), "2^3 · 5^2 · 11");
+///     assert_eq!(format!("{:#}", factor(2200)), "2^3*5^2*11");
+///     assert_eq!(factor(1).to_string(), "1");
+/// }
+/// ```
+impl std::fmt::Display for PrimeFactorization {
+    fn fmt(&self, w: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.facs.is_empty() {
+            return write!(w, "1");
+        }
+        let sep = if w.alternate() { "*" } else { " · " };
+        for (i, (p, pow)) in self.iter().enumerate() {
+            if i > 0 {
+                write!(w, "{}", sep)?;
+            }
+            write!(w, "{}", p)?;
+            if pow > 1 {
+                write!(w, "^{}", pow)?;
+            }
+        }
+        Ok(())
     }
 }
 
+/// Reports that removing factors from a `PrimeFactorization` would leave a negative exponent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FactorUnderflowError {
+    /// The prime whose exponent would go negative.
+    pub prime: Prime,
+    /// Its exponent in the factorization being reduced.
+    pub available: u64,
+    /// The exponent that was to be removed.
+    pub requested: u64,
+}
+
+impl std::fmt::Display for FactorUnderflowError {
+    fn fmt(&self, w: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(w, "cannot remove {}^{} from a factorization containing {}^{}",
+               self.prime, self.requested, self.prime, self.available)
+    }
+}
+
+impl std::error::Error for FactorUnderflowError {}
+
+/// Describes why a string could not be parsed as a `PrimeFactorization`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseFactorizationError {
+    /// The string, or one of the terms between separators, was empty.
+    Empty,
+    /// A base or exponent was not a valid u64.  Holds the offending text.
+    InvalidNumber(String),
+    /// A base was not prime.  Holds the base.
+    NotPrime(u64),
+    /// The exponents of a repeated base add up to more than a u64 can hold.  Holds the base.
+    ExponentOverflow(u64),
+}
+
+impl std::fmt::Display for ParseFactorizationError {
+    fn fmt(&self, w: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseFactorizationError::Empty => write!(w, "empty factorization or factor"),
+            ParseFactorizationError::InvalidNumber(s) => write!(w, "invalid number {:?}", s),
+            ParseFactorizationError::NotPrime(n) => write!(w, "{} is not prime", n),
+            ParseFactorizationError::ExponentOverflow(n) => write!(w, "exponent of {} overflows", n),
+        }
+    }
+}
+
+impl std::error::Error for ParseFactorizationError {}
+
+/// Parses a product of prime powers like `2^3*5*7^2`.
+///
+/// Factors are separated by `*` or `·`, and whitespace around them is ignored, so the output of
+/// both `Display` forms parses back to the same factorization.  Each base must be prime.  A base
+/// may appear more than once, in which case the exponents are added, failing if their sum
+/// overflows a u64.  `1` on its own is the empty factorization.
+///
+/// ```
+/// use red_primality::{ factor, PrimeFactorization, ParseFactorizationError };
+///
+/// fn main() {
+///     assert_eq!("2^3*5*7^2".parse(), Ok(factor(1960)));
+///     assert_eq!(factor(1960).to_string().parse(), Ok(factor(1960)));
+///     assert_eq!("1".parse(), Ok(PrimeFactorization::new()));
+///     assert_eq!("2^3*9".parse::<PrimeFactorization>(), Err(ParseFactorizationError::NotPrime(9)));
+/// }
+/// ```
+impl std::str::FromStr for PrimeFactorization {
+    type Err = ParseFactorizationError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |t: &str| {
+            let t = t.trim();
+            t.parse::<u64>().map_err(|_| ParseFactorizationError::InvalidNumber(t.to_string()))
+        };
+        if s.trim() == "1" {
+            return Ok(PrimeFactorization::new());
+        }
+        let mut pf = PrimeFactorization::new();
+        for term in s.split(['*', '·']) {
+            if term.trim().is_empty() {
+                return Err(ParseFactorizationError::Empty);
+            }
+            let (base, pow) = match term.split_once('^') {
+                Some((base, pow)) => (parse(base)?, parse(pow)?),
+                None => (parse(term)?, 1),
+            };
+            let p = Prime::new(base).ok_or(ParseFactorizationError::NotPrime(base))?;
+            pf.valuation(p).checked_add(pow).ok_or(ParseFactorizationError::ExponentOverflow(base))?;
+            pf.add(p, pow);
+        }
+        Ok(pf)
+    }
+}
+
+/// Sums the geometric series `1 + r + ... + r^e`, or returns `None` if it overflows a u128.
+fn geometric_sum(r: u128, e: u64) -> Option<u128> {
+    if r == 1 {
+        return e.checked_add(1).map(u128::from);
+    }
+    // (r^(e+1) - 1) / (r - 1), unless r^(e+1) alone overflows while the sum may not
+    let closed = e.checked_add(1).and_then(|e1| u32::try_from(e1).ok()).and_then(|e1| r.checked_pow(e1));
+    match closed {
+        Some(re1) => Some((re1 - 1) / (r - 1)),
+        None => {
+            // the sum is below r^(e+1), which is just past the limit, so add it up by Horner
+            let mut sum: u128 = 1;
+            for _ in 0..e {
+                sum = sum.checked_mul(r)?.checked_add(1)?;
+            }
+            Some(sum)
+        }
+    }
+}
+
+/// The number of partitions of `k`, by dynamic programming over the largest part, or `None` if
+/// it does not fit in a u64.
+///
+/// p(416) is the largest partition number below 2^64.  Every intermediate count is at most the
+/// final p(total) for its total, so nothing overflows up to there, and larger `k` are refused
+/// before allocating anything.
+fn partition_count(k: u64) -> Option<u64> {
+    if k > 416 {
+        return None;
+    }
+    let k = k as usize;
+    let mut counts = vec![0_u64; k + 1];
+    counts[0] = 1;
+    for part in 1..=k {
+        for total in part..=k {
+            counts[total] += counts[total - part];
+        }
+    }
+    Some(counts[k])
+}
+
 /// An incomplete factorization of a number.
 #[derive(Debug)]
 struct IncFac {
@@ -120,7 +1134,7 @@ impl IncFac {
         self.primes.add_pf(pf, 1);
     }
     fn done(&self) -> bool {
-        self.comps.len() == 0
+        self.comps.is_empty()
     }
     fn take(self) -> PrimeFactorization {
         assert!(self.done(), "Tried to use incomplete PrimeFactorization");
@@ -133,12 +1147,13 @@ impl IncFac {
     }
 }
 
-/// TODO: this will overflor for big trial primes.  This shouldn't happen, but fix it.
+/// Divides out the primes up to `limit`, returning what is left and the factors found.
 fn trial_div(mut n: u64, limit: u64) -> (u64, PrimeFactorization)
 {
     let mut ci = CertIter::all();
     let mut res = PrimeFactorization::new();
     assert!(n > 0, "trial_div trying to factor 0");
+    let mut root = isqrt(n);
     loop {
         if n == 1 {
             break;
@@ -148,14 +1163,17 @@ fn trial_div(mut n: u64, limit: u64) -> (u64, PrimeFactorization)
         if pp > limit {
             break;
         }
-        if pp * pp > n {
+        if pp > root {
             res.add(Prime::new(n).unwrap(), 1);
             n = 1;
             break;
         }
-        while n % pp == 0 {
-            res.add(p, 1);
-            n /= pp;
+        if n.is_multiple_of(pp) {
+            while n.is_multiple_of(pp) {
+                res.add(p, 1);
+                n /= pp;
+            }
+            root = isqrt(n);
         }
     }
     (n, res)
@@ -180,7 +1198,7 @@ fn rho_u128(fac: &mut IncFac, n64: u64, np: u64, r: u64)
             fac.add(n64, np);
             return;
         } else if g > 1 {
-            assert!(n % g == 0, "rho_u128, a={}, b={}, n={}, g={}, n%g={}",
+            assert!(n.is_multiple_of(g), "rho_u128, a={}, b={}, n={}, g={}, n%g={}",
                     a, b, n, g, n%g);
             let f = g as u64;
             fac.add(f, np);
@@ -208,19 +1226,34 @@ fn rho_u64(fac: &mut IncFac, n64: u64, np: u64, r: u64)
             fac.add(n64, np);
             return;
         } else if g > 1 {
-            assert!(n % g == 0, "rho_u128, a={}, b={}, n={}, g={}, n%g={}",
+            assert!(n.is_multiple_of(g), "rho_u128, a={}, b={}, n={}, g={}, n%g={}",
                     a, b, n, g, n%g);
-            let f = g as u64;
+            let f = g;
             fac.add(f, np);
             fac.add(n64/f, np);
             return;
         }
     }
 }
+/// Finds `n = root^k` with `k >= 2` and the smallest possible root, if `n` is a perfect power.
+fn perfect_power(n: u64) -> Option<(u64, u64)> {
+    (2..64)
+        .map(|k| (nth_root(n, k), k))
+        .take_while(|&(root, _)| root >= 2)
+        .filter(|&(root, k)| root.pow(k) == n)
+        .last()
+        .map(|(root, k)| (root, u64::from(k)))
+}
+
 fn rho_step(fac: &mut IncFac, r: u64) {
     let (n64, np) = fac.take_composite().unwrap();
-    let n = n64 as u128;
-    if n*n + (r as u128) < (std::u64::MAX as u128) {
+    // taking the root of a perfect power is much quicker than waiting for rho to find it
+    if let Some((root, k)) = perfect_power(n64) {
+        fac.add(root, np * k);
+        return;
+    }
+    // a^2 + r must fit in a u64 for every residue a < n
+    if n64 <= isqrt(u64::MAX - r) {
         rho_u64(fac, n64, np, r);
     } else {
         rho_u128(fac, n64, np, r);
@@ -266,32 +1299,316 @@ pub fn factor(n: u64) -> PrimeFactorization
 
 }
 
-/// Euler's totient function
+/// Factors the greatest common divisor of `a` and `b`.
+///
+/// The gcd is found with Euclid's algorithm first, so only one, smaller, number gets factored.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::{ factor, gcd_factored };
+///
+/// fn main() {
+///     assert_eq!(gcd_factored(360, 84), factor(12));
+///     assert_eq!(gcd_factored(0, 84), factor(84));
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `a` and `b` are both 0.
+pub fn gcd_factored(a: u64, b: u64) -> PrimeFactorization {
+    use num::Integer;
+    factor(a.gcd(&b))
+}
+
+/// Factors the least common multiple of `a` and `b`, which may be too large for a u64.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::{ factor, lcm_factored };
+///
+/// fn main() {
+///     assert_eq!(lcm_factored(360, 84), factor(2520));
+///     let big = lcm_factored(u64::MAX, u64::MAX - 1);
+///     assert_eq!(big, factor(u64::MAX) * factor(u64::MAX - 1));
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `a` or `b` is 0.
+pub fn lcm_factored(a: u64, b: u64) -> PrimeFactorization {
+    factor(a).lcm(&factor(b))
+}
+
+/// Euler's totient function
+///
+/// Factors `n` and uses the factorization to calculate the totient function.
+pub fn euler_totient(n: u64) -> u64 {
+    factor(n).euler_totient()
+}
+
+/// Jordan's totient function
+///
+/// Factors `n` and uses the factorization to calculate J_k(n), the number of `k`-tuples of
+/// integers in `1..=n` whose gcd together with `n` is 1.  J_1 is Euler's totient function.
+///
+/// Intermediate values are u128, so the result is exact for all `n` when `k <= 2`.  Returns
+/// `None` if the result does not fit in a u128.
+pub fn jordan_totient(n: u64, k: u32) -> Option<u128> {
+    factor(n).jordan_totient(k)
+}
+
+/// Divisor count function
+///
+/// Factors `n` and uses the factorization to calculate d(n), the number of divisors of `n`.
+pub fn divisor_count(n: u64) -> u64 {
+    factor(n).divisor_count()
+}
+
+/// Divisor sum function
+///
+/// Factors `n` and uses the factorization to calculate σ(n), the sum of the divisors of `n`.
+///
+/// # Panics
+///
+/// Panics if the result does not fit in a u64.  Use `checked_divisor_sum()` to avoid this.
+pub fn divisor_sum(n: u64) -> u64 {
+    factor(n).divisor_sum()
+}
+
+/// Divisor sum function, returning `None` if the result does not fit in a u64.
+pub fn checked_divisor_sum(n: u64) -> Option<u64> {
+    factor(n).checked_divisor_sum()
+}
+
+/// Generalized divisor function
+///
+/// Factors `n` and uses the factorization to calculate σ_k(n), the sum of the `k`-th powers of
+/// the divisors of `n`.
+///
+/// # Panics
+///
+/// Panics if the result does not fit in a u64.  Use `checked_sigma_k()` to avoid this.
+pub fn sigma_k(n: u64, k: u32) -> u64 {
+    factor(n).sigma_k(k)
+}
+
+/// Generalized divisor function, returning `None` if the result does not fit in a u64.
+pub fn checked_sigma_k(n: u64, k: u32) -> Option<u64> {
+    factor(n).checked_sigma_k(k)
+}
+
+/// Generalized divisor function in u128 arithmetic, returning `None` if the result does not fit
+/// in a u128.
+pub fn sigma_k_u128(n: u64, k: u32) -> Option<u128> {
+    factor(n).sigma_k_u128(k)
+}
+
+/// Unitary divisor sum function
+///
+/// Factors `n` and uses the factorization to calculate σ*(n), the sum of the divisors `d` of `n`
+/// with `gcd(d, n / d) = 1`.
+///
+/// # Panics
+///
+/// Panics if the result does not fit in a u64.  Use `checked_unitary_divisor_sum()` to avoid
+/// this.
+pub fn unitary_divisor_sum(n: u64) -> u64 {
+    factor(n).unitary_divisor_sum()
+}
+
+/// Unitary divisor sum function, returning `None` if the result does not fit in a u64.
+pub fn checked_unitary_divisor_sum(n: u64) -> Option<u64> {
+    factor(n).checked_unitary_divisor_sum()
+}
+
+/// Unitary totient function
 ///
-/// Factors `n` and uses the factorization to calculate the totient function.
-pub fn euler_totient(n: u64) -> u64 {
-    factor(n).euler_totient()
+/// Factors `n` and uses the factorization to calculate φ*(n), the product of `p^e - 1` over the
+/// prime powers exactly dividing `n`.
+pub fn unitary_totient(n: u64) -> u64 {
+    factor(n).unitary_totient()
+}
+
+/// Carmichael's function
+///
+/// Factors `n` and uses the factorization to calculate λ(n), the smallest `m` such that
+/// `a^m ≡ 1 (mod n)` for every `a` coprime to `n`.
+pub fn carmichael_lambda(n: u64) -> u64 {
+    factor(n).carmichael_lambda()
 }
 
 /// Möbius function
 ///
-/// Given `x` and `y`, calculates the Möbius function of `x`/`y`.
+/// Factors `n` and calculates μ(n): 0 if `n` has a squared prime factor, otherwise 1 or -1 for an
+/// even or odd number of prime factors.  μ(0) is taken to be 0.
+pub fn mobius_n(n: u64) -> i64 {
+    if n == 0 {
+        0
+    } else {
+        factor(n).mobius()
+    }
+}
+
+/// Möbius function of a ratio
+///
+/// Given `x` and `y`, calculates the Möbius function of `x`/`y`.  This is 0 when `y` doesn't
+/// divide `x`, and when `x` is 0.
 ///
 /// # Panics
 ///
 /// Panics when y is zero.
-pub fn mobius(x: u64, y: u64) -> i64 {
+pub fn mobius_ratio(x: u64, y: u64) -> i64 {
     if x == 0 {
         0
     } else if y == 0 {
         panic!("Tried to calculate mobius function of {}/{}", x, y);
-    } else if x % y != 0 {
+    } else if !x.is_multiple_of(y) {
         0
     } else {
         factor(x/y).mobius()
     }
 }
 
+/// Möbius function of a ratio
+///
+/// The same as `mobius_ratio()`, kept under its original name.  Use `mobius_n()` for μ(n).
+///
+/// # Panics
+///
+/// Panics when y is zero.
+pub fn mobius(x: u64, y: u64) -> i64 {
+    mobius_ratio(x, y)
+}
+
+/// Radical of an integer
+///
+/// Factors `n` and returns the product of its distinct prime factors, also known as its
+/// squarefree kernel.
+///
+/// # Panics
+///
+/// This function will panic if `n` is 0.
+pub fn radical(n: u64) -> u64 {
+    factor(n).radical()
+}
+
+/// Squarefree decomposition
+///
+/// Factors `n` and returns `(s, f)` such that `n = s * f^2` and `s` is squarefree.  `f^2` is
+/// the largest square dividing `n`.
+///
+/// # Panics
+///
+/// This function will panic if `n` is 0.
+pub fn squarefree_decompose(n: u64) -> (u64, u64) {
+    factor(n).squarefree_decompose()
+}
+
+/// Determines whether `n` is `k`-free, meaning no `k`-th power other than 1 divides it.
+///
+/// 2-free numbers are the squarefree numbers, and 3-free numbers the cubefree numbers.  0 is
+/// never `k`-free, since every power divides it.
+///
+/// # Panics
+///
+/// Panics if `k` is 0.
+pub fn is_k_free(n: u64, k: u32) -> bool {
+    assert!(k > 0, "is_k_free called with k = 0");
+    n != 0 && factor(n).is_k_free(k)
+}
+
+/// Determines whether `n` is squarefree, meaning no square other than 1 divides it.
+///
+/// 0 is not squarefree.
+pub fn is_squarefree(n: u64) -> bool {
+    is_k_free(n, 2)
+}
+
+/// Determines whether `p` is a Blum prime, a prime congruent to 3 modulo 4.
+pub fn is_blum_prime(p: u64) -> bool {
+    p & 3 == 3 && is_u64_prime(p)
+}
+
+/// Determines whether `n` is a Blum integer, the product of two distinct Blum primes.
+///
+/// Factors `n` to check.  Blum integers are the moduli of the Blum-Blum-Shub generator and the
+/// Rabin cryptosystem, where every quadratic residue has exactly one square root that is itself
+/// a quadratic residue.
+pub fn is_blum_integer(n: u64) -> bool {
+    if n < 21 || n & 3 != 1 {
+        return false;
+    }
+    let facs: Vec<(Prime, u64)> = factor(n).iter().collect();
+    facs.len() == 2 && facs.iter().all(|&(p, e)| e == 1 && p.get() & 3 == 3)
+}
+
+/// Determines whether `n` is a power of a single prime.
+///
+/// Returns `Some((p, k))` with `n = p^k` and `k >= 1`, or `None` if `n` has more than one
+/// distinct prime factor.  0 and 1 are not prime powers.  Unlike a general perfect power test,
+/// this accepts primes themselves and rejects powers of composites like `36 = 6^2`.
+pub fn is_prime_power(n: u64) -> Option<(Prime, u32)> {
+    if n < 2 {
+        return None;
+    }
+    let pf = factor(n);
+    let mut it = pf.iter();
+    match (it.next(), it.next()) {
+        (Some((p, pow)), None) => Some((p, pow as u32)),
+        _ => None,
+    }
+}
+
+/// p-adic valuation
+///
+/// Returns the exponent of `p` in `n`, the largest `k` such that `p^k` divides `n`.  This is
+/// computed by repeated division, without factoring, so `p` may also be composite: for example
+/// `valuation(72, 6)` is 2, since 36 divides 72 but 216 doesn't.  For composite `p` this is the
+/// minimum of `v_q(n) / v_q(p)`, rounded down, over the prime factors `q` of `p`.
+///
+/// # Panics
+///
+/// Panics if `n` is 0, since every power of `p` divides 0, or if `p` is less than 2.
+pub fn valuation(n: u64, p: u64) -> u32 {
+    assert!(n != 0, "valuation of 0 is infinite");
+    assert!(p >= 2, "valuation called with base {}", p);
+    let mut n = n;
+    let mut k = 0;
+    while n.is_multiple_of(p) {
+        n /= p;
+        k += 1;
+    }
+    k
+}
+
+/// Number of abelian groups of order `n`
+///
+/// Factors `n` and multiplies together the partition numbers of the exponents, since every
+/// finite abelian group is a product of cyclic groups of prime power order.
+///
+/// # Panics
+///
+/// This function will panic if `n` is 0.
+pub fn abelian_group_count(n: u64) -> u64 {
+    factor(n).abelian_group_count()
+}
+
+/// Liouville function
+///
+/// Factors `n` and uses the factorization to calculate λ(n) = (-1)^Ω(n), where Ω(n) is the
+/// number of prime factors of `n` counted with multiplicity.
+///
+/// # Panics
+///
+/// This function will panic if `n` is 0.
+pub fn liouville(n: u64) -> i64 {
+    factor(n).liouville()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -326,7 +1643,7 @@ mod tests {
     #[test]
     fn factor_bigs() {
         let radius = 100;
-        for n in std::u64::MAX - radius..=std::u64::MAX {
+        for n in u64::MAX - radius..=u64::MAX {
             test_factor(n, false);
         }
     }
@@ -352,6 +1669,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn factor_perfect_powers() {
+        assert_eq!(perfect_power(36), Some((6, 2)));
+        assert_eq!(perfect_power(64), Some((2, 6)));
+        assert_eq!(perfect_power(1 << 63), Some((2, 63)));
+        assert_eq!(perfect_power(12), None);
+        assert_eq!(perfect_power(u64::MAX), None);
+        let p = prev_prime(1 << 32).unwrap();
+        let q = prev_prime(1 << 16).unwrap();
+        let r = prev_prime(q.get()).unwrap();
+        for (n, expected) in [(p.get() * p.get(), vec![(p, 2)]),
+                              (q.get().pow(4), vec![(q, 4)]),
+                              ((q.get() * r.get()).pow(2), vec![(r, 2), (q, 2)])] {
+            let pf = test_factor(n, false);
+            assert_eq!(pf.iter().collect::<Vec<(Prime, u64)>>(), expected, "factor({})", n);
+        }
+    }
+
     fn brute_force_totient(n: u64) -> u64 {
         use num::Integer;
         let mut res = 0;
@@ -376,10 +1711,52 @@ mod tests {
         }
     }
 
+    fn brute_force_lambda(n: u64) -> u64 {
+        use num::Integer;
+        let units: Vec<u64> = (1..=n).filter(|a| a.gcd(&n) == 1).collect();
+        (1..=n).find(|&m| units.iter().all(|&a| {
+            let mut x = 1 % n;
+            for _ in 0..m {
+                x = x * a % n;
+            }
+            x == 1 % n
+        })).unwrap()
+    }
+
+    #[test]
+    fn small_lambdas() {
+        for i in 1..300 {
+            assert_eq!(carmichael_lambda(i), brute_force_lambda(i), "carmichael_lambda({})", i);
+        }
+    }
+
+    #[test]
+    fn small_jordan_totients() {
+        use num::Integer;
+        for i in 1..=60 {
+            let pairs = (1..=i).flat_map(|a| (1..=i).map(move |b| (a, b)))
+                .filter(|(a, b)| a.gcd(b).gcd(&i) == 1)
+                .count();
+            assert_eq!(jordan_totient(i, 2), Some(pairs as u128), "jordan_totient({}, 2)", i);
+        }
+        for i in 1..=500 {
+            assert_eq!(jordan_totient(i, 1), Some(euler_totient(i) as u128));
+            for k in 0..5 {
+                // J_k summed over the divisors of n is n^k.
+                let sum: u128 = brute_force_divisors(i).into_iter()
+                    .map(|d| jordan_totient(d, k).unwrap())
+                    .sum();
+                assert_eq!(sum, (i as u128).pow(k), "jordan_totient sum for {}, k={}", i, k);
+            }
+        }
+        assert_eq!(jordan_totient(MAX_U64_PRIME, 2), Some((MAX_U64_PRIME as u128).pow(2) - 1));
+        assert_eq!(jordan_totient(MAX_U64_PRIME, 3), None);
+    }
+
     fn brute_force_divisors(n: u64) -> BTreeSet<u64> {
         let mut res = BTreeSet::new();
         for i in 1..=n {
-            if n % i == 0 {
+            if n.is_multiple_of(i) {
                 res.insert(i);
             }
         }
@@ -395,6 +1772,496 @@ mod tests {
         let d1 = brute_force_divisors(n);
         let d2 = fast_divisors(n);
         assert_eq!(d1, d2, "test_divisorss({})", n);
+        let d3: Vec<u64> = factor(n).divisors().collect();
+        assert_eq!(d3.len(), d1.len(), "divisors({}) repeated something", n);
+        assert_eq!(d1, d3.into_iter().collect(), "divisors({})", n);
+        let d4: Vec<u64> = factor(n).divisors_sorted().collect();
+        let pairs: Vec<(u64, u64)> = factor(n).divisor_pairs().collect();
+        let expected: Vec<(u64, u64)> = d1.iter().filter(|&&d| d * d <= n).map(|&d| (d, n / d)).collect();
+        assert_eq!(pairs, expected, "divisor_pairs({})", n);
+        assert_eq!(d1.iter().copied().collect::<Vec<u64>>(), d4, "divisors_sorted({})", n);
+        assert_eq!(d1.len() as u64, divisor_count(n), "divisor_count({})", n);
+    }
+
+    #[test]
+    fn small_sigmas() {
+        for i in 1..=1000 {
+            let divs = brute_force_divisors(i);
+            for k in 0..4 {
+                let expected: u64 = divs.iter().map(|d| d.pow(k)).sum();
+                assert_eq!(sigma_k(i, k), expected, "sigma_k({}, {})", i, k);
+            }
+            assert_eq!(divisor_sum(i), divs.iter().sum(), "divisor_sum({})", i);
+        }
+    }
+
+    #[test]
+    fn divisor_count_overflow() {
+        assert_eq!(factor(1).checked_divisor_count(), Some(1));
+        assert_eq!(factor(614_889_782_588_491_410).checked_divisor_count(), Some(1 << 15));
+        assert_eq!(factor(963_761_198_400).divisor_count(), 6720);
+        let e = u32::MAX as u64;
+        assert_eq!(factor(6).pow(e - 1).checked_divisor_count(), Some(e * e));
+        assert_eq!(factor(6).pow(e).checked_divisor_count(), None);
+        assert_eq!(factor(2).pow(u64::MAX).checked_divisor_count(), None);
+    }
+
+    #[test]
+    fn factored_divisors() {
+        for n in 1..=1000 {
+            let pf = factor(n);
+            let pfs: Vec<PrimeFactorization> = pf.divisor_factorizations().collect();
+            let expected: Vec<PrimeFactorization> = pf.divisors().map(factor).collect();
+            assert_eq!(pfs, expected, "divisor_factorizations({})", n);
+        }
+        let big = factor(u64::MAX).pow(3);
+        assert_eq!(big.divisor_factorizations().count() as u64, big.divisor_count());
+        assert_eq!(big.divisor_factorizations().last(), Some(big.clone()));
+        assert!(big.divisor_factorizations().all(|d| d.divides(&big)));
+    }
+
+    #[test]
+    fn unitary_functions() {
+        use num::Integer;
+        for n in 1..=1000_u64 {
+            let expected: Vec<u64> = brute_force_divisors(n).into_iter().filter(|d| d.gcd(&(n / d)) == 1).collect();
+            let mut divs: Vec<u64> = factor(n).unitary_divisors().collect();
+            divs.sort_unstable();
+            assert_eq!(divs, expected, "unitary_divisors({})", n);
+            assert_eq!(unitary_divisor_sum(n), expected.iter().sum(), "unitary_divisor_sum({})", n);
+            let coprime = (1..=n).filter(|&k| expected.iter().all(|&d| d == 1 || !k.is_multiple_of(d))).count();
+            assert_eq!(unitary_totient(n), coprime as u64, "unitary_totient({})", n);
+        }
+        assert_eq!(unitary_totient(u64::MAX), factor(u64::MAX).euler_totient());
+        assert_eq!(unitary_totient(1 << 63), (1 << 63) - 1);
+        assert_eq!(checked_unitary_divisor_sum(1 << 63), Some((1 << 63) + 1));
+        assert_eq!(checked_unitary_divisor_sum(u64::MAX), None);
+        assert_eq!(factor(614_889_782_588_491_410).unitary_divisors().count(), 1 << 15);
+    }
+
+    #[test]
+    fn sigma_overflow() {
+        assert_eq!(checked_divisor_sum(1 << 63), Some(u64::MAX));
+        assert_eq!(checked_divisor_sum(3 << 62), None);
+        assert_eq!(checked_sigma_k(MAX_U64_PRIME, 1), Some(MAX_U64_PRIME + 1));
+        assert_eq!(checked_sigma_k(MAX_U64_PRIME, 0), Some(2));
+        assert_eq!(checked_sigma_k(1 << 32, 2), None);
+        assert_eq!(sigma_k_u128(1 << 32, 2), Some(((1 << 66) - 1) / 3));
+        assert_eq!(factor(3 << 62).divisor_sum_u128(), Some(4 * ((1 << 63) - 1)));
+        assert_eq!(sigma_k_u128(u64::MAX, 1), Some(factor(u64::MAX).iter().map(|(p, _)| u128::from(p.get()) + 1).product()));
+        assert_eq!(sigma_k_u128(u64::MAX, 2), None);
+        assert_eq!(sigma_k_u128(MAX_U64_PRIME, 2), Some(u128::from(MAX_U64_PRIME).pow(2) + 1));
+        assert_eq!(sigma_k_u128(MAX_U64_PRIME, 3), None);
+        // the closed form overflows here even though the sum doesn't
+        assert_eq!(factor(2).pow(127).divisor_sum_u128(), Some(u128::MAX));
+        assert_eq!(factor(2).pow(128).divisor_sum_u128(), None);
+        assert_eq!(factor(2).pow(1000).sigma_k_u128(0), Some(1001));
+        let mut huge = PrimeFactorization::new();
+        huge.add(Prime::new(3).unwrap(), u64::MAX);
+        assert_eq!(huge.sigma_k_u128(0), None);
+        assert_eq!(huge.sigma_k_u128(1), None);
+    }
+
+    #[test]
+    fn u128_sigmas() {
+        for i in 1..=1000 {
+            let divs = brute_force_divisors(i);
+            for k in 0..8 {
+                let expected: u128 = divs.iter().map(|&d| u128::from(d).pow(k)).sum();
+                assert_eq!(sigma_k_u128(i, k), Some(expected), "sigma_k_u128({}, {})", i, k);
+                assert_eq!(checked_sigma_k(i, k), u64::try_from(expected).ok(), "checked_sigma_k({}, {})", i, k);
+            }
+        }
+    }
+
+    #[test]
+    fn small_mobius() {
+        for i in 1..=1000 {
+            // The sum of μ(d) over divisors is 1 for n = 1 and 0 otherwise.
+            let sum: i64 = brute_force_divisors(i).into_iter().map(mobius_n).sum();
+            assert_eq!(sum, if i == 1 { 1 } else { 0 }, "mobius divisor sum for {}", i);
+            assert_eq!(mobius_ratio(6 * i, 6), mobius_n(i));
+            assert_eq!(mobius(6 * i, 6), mobius_n(i));
+        }
+        assert_eq!(mobius_n(0), 0);
+        assert_eq!(mobius_ratio(0, 5), 0);
+        assert_eq!(mobius_ratio(10, 4), 0);
+    }
+
+    #[test]
+    fn valuations() {
+        for n in 1..=2000 {
+            let pf = factor(n);
+            for p in [2, 3, 5, 7, 11, 13].iter() {
+                let v = valuation(n, *p);
+                assert_eq!(v as u64, pf.valuation(Prime::new(*p).unwrap()));
+                assert!(n.is_multiple_of(p.pow(v)) && !n.is_multiple_of(p.pow(v + 1)));
+            }
+        }
+        assert_eq!(valuation(72, 6), 2);
+        assert_eq!(valuation(1 << 63, 2), 63);
+        assert_eq!(valuation(u64::MAX, 4), 0);
+        assert_eq!(valuation(3_u64.pow(40), 3), 40);
+        assert_eq!(valuation(3_u64.pow(40), 9), 20);
+    }
+
+    #[test]
+    fn abelian_groups() {
+        // OEIS A000688
+        let counts: Vec<u64> = (1..=32).map(abelian_group_count).collect();
+        assert_eq!(counts, vec![1, 1, 1, 2, 1, 1, 1, 3, 2, 1, 1, 2, 1, 1, 1, 5,
+                                1, 2, 1, 2, 1, 1, 1, 3, 2, 1, 3, 2, 1, 1, 1, 7]);
+        // OEIS A000041
+        let partitions: Vec<u64> = (0..=12).map(|k| partition_count(k).unwrap()).collect();
+        assert_eq!(partitions, vec![1, 1, 2, 3, 5, 7, 11, 15, 22, 30, 42, 56, 77]);
+        assert_eq!(partition_count(63), Some(1_505_499));
+        assert_eq!(partition_count(405), Some(9_147_679_068_859_117_602));
+        assert_eq!(partition_count(416).map(|p| p > u64::MAX / 2), Some(true));
+        assert_eq!(partition_count(417), None);
+        assert_eq!(partition_count(u64::MAX), None);
+        // factorizations can carry exponents far beyond 63
+        assert_eq!(factor_factorial(1000).checked_abelian_group_count(), None);
+        assert_eq!(factor(2).pow(416).checked_abelian_group_count(), partition_count(416));
+        assert_eq!(factor(6).pow(405).checked_abelian_group_count(), None);
+        assert_eq!(factor(2).pow(u64::MAX).checked_abelian_group_count(), None);
+        assert_eq!(abelian_group_count(1 << 63), 1_505_499);
+        assert_eq!(abelian_group_count(720), 5 * 2);
+    }
+
+    #[test]
+    fn k_free() {
+        for n in 1..=2000_u64 {
+            for k in 1..=4 {
+                let expected = (2..=n).all(|d| !n.is_multiple_of(d.pow(k)));
+                assert_eq!(is_k_free(n, k), expected, "is_k_free({}, {})", n, k);
+            }
+            assert_eq!(is_squarefree(n), mobius_n(n) != 0);
+        }
+        assert!(!is_k_free(0, 5));
+        assert!(is_k_free(1 << 62, 63));
+        assert!(!is_k_free(1 << 63, 63));
+    }
+
+    #[test]
+    fn blum() {
+        let blum_primes: Vec<u64> = (0..100).filter(|&p| is_blum_prime(p)).collect();
+        assert_eq!(blum_primes, vec![3, 7, 11, 19, 23, 31, 43, 47, 59, 67, 71, 79, 83]);
+        // OEIS A016105
+        let blum_ints: Vec<u64> = (0..250).filter(|&n| is_blum_integer(n)).collect();
+        assert_eq!(blum_ints, vec![21, 33, 57, 69, 77, 93, 129, 133, 141, 161, 177, 201, 209, 213, 217, 237, 249]);
+        for n in 0..5000 {
+            let brute = (3..n).any(|p| is_blum_prime(p) && n % p == 0 && n / p > p && is_blum_prime(n / p));
+            assert_eq!(is_blum_integer(n), brute, "is_blum_integer({})", n);
+        }
+        assert!(is_blum_integer(4_294_967_291 * 4_294_967_279));
+        assert!(!is_blum_integer(4_294_967_291 * 4_294_967_291));
+    }
+
+    #[test]
+    fn small_liouvilles() {
+        for i in 1..=1000 {
+            // The sum of λ(d) over divisors is 1 for squares and 0 otherwise.
+            let sum: i64 = brute_force_divisors(i).into_iter().map(liouville).sum();
+            let is_square = (1..=i).any(|r| r * r == i);
+            assert_eq!(sum, if is_square { 1 } else { 0 }, "liouville divisor sum for {}", i);
+        }
+        assert_eq!(liouville(1), 1);
+        assert_eq!(liouville(12), -1);
+        assert_eq!(liouville(u64::MAX), -1);
+    }
+
+    #[test]
+    fn small_radicals() {
+        for i in 1..=1000 {
+            let rad = radical(i);
+            let primes: Vec<u64> = (2..=i).filter(|&p| i.is_multiple_of(p) && is_u64_prime(p)).collect();
+            assert_eq!(rad, primes.iter().product(), "radical({})", i);
+        }
+        assert_eq!(radical(1 << 63), 2);
+        assert_eq!(radical(u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn small_squarefree_decompositions() {
+        for i in 1..=1000 {
+            let (s, f) = squarefree_decompose(i);
+            assert_eq!(s * f * f, i, "squarefree_decompose({})", i);
+            assert_eq!(mobius(s, 1).abs(), 1, "squarefree_decompose({}) = ({}, {})", i, s, f);
+            let largest = (1..=i).filter(|r| i.is_multiple_of(r * r)).max().unwrap();
+            assert_eq!(f, largest, "squarefree_decompose({})", i);
+        }
+        assert_eq!(squarefree_decompose(1 << 63), (2, 1 << 31));
+    }
+
+    #[test]
+    fn prime_powers() {
+        let brute = |n: u64| -> Option<(u64, u32)> {
+            let p = (2..=n).find(|d| n.is_multiple_of(*d))?;
+            let mut m = n;
+            let mut k = 0;
+            while m.is_multiple_of(p) {
+                m /= p;
+                k += 1;
+            }
+            if m == 1 { Some((p, k)) } else { None }
+        };
+        for i in 0..=1000 {
+            assert_eq!(is_prime_power(i).map(|(p, k)| (p.get(), k)), brute(i), "is_prime_power({})", i);
+        }
+        assert_eq!(is_prime_power(36), None);
+        assert_eq!(is_prime_power(1 << 63).map(|(p, k)| (p.get(), k)), Some((2, 63)));
+        assert_eq!(is_prime_power(MAX_U64_PRIME).map(|(p, k)| (p.get(), k)), Some((MAX_U64_PRIME, 1)));
+        assert_eq!(is_prime_power(4294967291 * 4294967291).map(|(p, k)| (p.get(), k)),
+                   Some((4294967291, 2)));
+    }
+
+    #[test]
+    fn collect_factorizations() {
+        for a in 1..200 {
+            let pf: PrimeFactorization = factor(a).iter().collect();
+            assert_eq!(pf, factor(a));
+            for b in 1..200 {
+                let mut merged = factor(a);
+                merged.extend(factor(b).iter());
+                assert_eq!(merged, factor(a * b), "{} * {}", a, b);
+            }
+        }
+        let zero: PrimeFactorization = vec![(Prime::new(5).unwrap(), 0)].into_iter().collect();
+        assert_eq!(zero, PrimeFactorization::new());
+    }
+
+    #[test]
+    fn multiply_factorizations() {
+        for a in 1..300 {
+            for b in 1..300 {
+                let (fa, fb) = (factor(a), factor(b));
+                assert_eq!(&fa * &fb, factor(a * b), "{} * {}", a, b);
+                let mut prod = fa.clone();
+                prod *= fb.clone();
+                assert_eq!(prod, fa * fb);
+            }
+        }
+    }
+
+    #[test]
+    fn divide_factorizations() {
+        for a in 1..300 {
+            for b in 1..300 {
+                let (fa, fb) = (factor(a), factor(b));
+                assert_eq!(fb.divides(&fa), a % b == 0, "{} | {}", b, a);
+                assert_eq!(fa.try_div(&fb), if a % b == 0 { Some(factor(a / b)) } else { None });
+            }
+        }
+    }
+
+    #[test]
+    fn factored_gcd_lcm() {
+        use num::Integer;
+        for a in 1..300_u64 {
+            for b in 1..300 {
+                let (fa, fb) = (factor(a), factor(b));
+                assert_eq!(fa.gcd(&fb), factor(a.gcd(&b)), "gcd({}, {})", a, b);
+                assert_eq!(fa.lcm(&fb), factor(a.lcm(&b)), "lcm({}, {})", a, b);
+                assert_eq!(gcd_factored(a, b), factor(a.gcd(&b)));
+                assert_eq!(lcm_factored(a, b), factor(a.lcm(&b)));
+            }
+        }
+        assert_eq!(gcd_factored(17, 0), factor(17));
+    }
+
+    #[test]
+    fn powers_and_roots() {
+        for n in 1..1000_u64 {
+            let pf = factor(n);
+            assert_eq!(pf.pow(0), PrimeFactorization::new());
+            assert_eq!(pf.pow(1), pf);
+            for k in 1..4 {
+                if let Some(m) = n.checked_pow(k) {
+                    assert_eq!(pf.pow(k as u64), factor(m));
+                }
+                let root = (1..=n).find(|r| r.pow(k) == n);
+                assert_eq!(pf.nth_root(k as u64), root.map(factor), "{}-th root of {}", k, n);
+            }
+            assert_eq!(pf.pow(60).nth_root(60), Some(pf.clone()));
+        }
+        assert_eq!(factor(2).pow(u64::MAX).valuation(Prime::new(2).unwrap()), u64::MAX);
+        assert_eq!(factor(4).checked_pow(u64::MAX), None);
+    }
+
+    #[test]
+    fn kth_powers() {
+        for n in 1..10_000_u64 {
+            let pf = factor(n);
+            assert_eq!(pf.is_square(), is_perfect_square(n), "{}", n);
+            for k in 1..5 {
+                assert_eq!(pf.is_kth_power(k as u64), is_kth_power(n, k), "{}, {}", n, k);
+            }
+        }
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(factor(1).to_string(), "1");
+        assert_eq!(factor(2).to_string(), "2");
+        assert_eq!(factor(360).to_string(), "2^3 · 3^2 · 5");
+        assert_eq!(format!("{:#}", factor(360)), "2^3*3^2*5");
+        assert_eq!(format!("{:#}", factor(1)), "1");
+        assert_eq!(factor(1 << 63).to_string(), "2^63");
+        assert_eq!(format!("{:#}", factor(u64::MAX)), "3*5*17*257*641*65537*6700417");
+    }
+
+    #[test]
+    fn parse() {
+        for n in 1..10_000 {
+            let pf = factor(n);
+            assert_eq!(pf.to_string().parse(), Ok(pf.clone()));
+            assert_eq!(format!("{:#}", pf).parse(), Ok(pf));
+        }
+        assert_eq!(" 3 ^ 2 * 2 * 3 ".parse(), Ok(factor(54)));
+        assert_eq!("2^0".parse(), Ok(factor(1)));
+        assert_eq!("18446744073709551557".parse(), Ok(factor(MAX_U64_PRIME)));
+        let err = |s: &str| s.parse::<PrimeFactorization>().unwrap_err();
+        assert_eq!(err(""), ParseFactorizationError::Empty);
+        assert_eq!(err("2**3"), ParseFactorizationError::Empty);
+        assert_eq!(err("2^"), ParseFactorizationError::InvalidNumber("".to_string()));
+        assert_eq!(err("2^3^4"), ParseFactorizationError::InvalidNumber("3^4".to_string()));
+        assert_eq!(err("x"), ParseFactorizationError::InvalidNumber("x".to_string()));
+        assert_eq!(err("-2"), ParseFactorizationError::InvalidNumber("-2".to_string()));
+        assert_eq!(err("1*2"), ParseFactorizationError::NotPrime(1));
+        assert_eq!(err("0"), ParseFactorizationError::NotPrime(0));
+        assert_eq!(err("15^2"), ParseFactorizationError::NotPrime(15));
+        assert_eq!(err("2^18446744073709551615*2"), ParseFactorizationError::ExponentOverflow(2));
+        let two = Prime::new(2).unwrap();
+        assert_eq!("2^18446744073709551614*2".parse().map(|pf: PrimeFactorization| pf.valuation(two)),
+                   Ok(u64::MAX));
+    }
+
+    #[test]
+    fn exponent_lookup() {
+        for n in 1..2000 {
+            let pf = factor(n);
+            for p in CertIter::from(0).take_while(|p| p.get() <= n + 10) {
+                let mut e = 0;
+                let mut m = n;
+                while m.is_multiple_of(p.get()) {
+                    m /= p.get();
+                    e += 1;
+                }
+                assert_eq!(pf.exponent_of(p), e);
+                assert_eq!(pf[p], e);
+                assert_eq!(pf.contains(p), e > 0);
+            }
+        }
+    }
+
+    #[test]
+    fn remove_factors() {
+        for a in 1..300 {
+            let fa = factor(a);
+            for b in 1..300 {
+                let mut pf = fa.clone();
+                let res = pf.sub_pf(&factor(b));
+                if a % b == 0 {
+                    assert_eq!(res, Ok(()));
+                    assert_eq!(pf, factor(a / b));
+                } else {
+                    let err = res.unwrap_err();
+                    assert_eq!(pf, fa);
+                    assert!(err.available < err.requested);
+                    assert_eq!(err.available, fa.valuation(err.prime));
+                }
+            }
+        }
+        let two = Prime::new(2).unwrap();
+        let mut pf = factor(24);
+        assert_eq!(pf.remove(two, 0), Ok(()));
+        assert_eq!(pf.remove(two, 2), Ok(()));
+        assert_eq!(pf, factor(6));
+        assert_eq!(pf.remove(two, 2), Err(FactorUnderflowError { prime: two, available: 1, requested: 2 }));
+        assert_eq!(pf.remove(two, 1), Ok(()));
+        assert_eq!(pf, factor(3));
+        assert!(!pf.contains(two));
+        assert_eq!(pf.remove(two, 0), Ok(()));
+        assert_eq!(pf, factor(3));
+    }
+
+    #[test]
+    fn checked_products() {
+        for n in 1..10_000 {
+            assert_eq!(factor(n).checked_product(), Some(n));
+        }
+        let two = Prime::new(2).unwrap();
+        let mut pf = PrimeFactorization::new();
+        pf.add(two, 63);
+        assert_eq!(pf.checked_product(), Some(1 << 63));
+        pf.add(two, 1);
+        assert_eq!(pf.checked_product(), None);
+        pf.add(two, u64::from(u32::MAX) * 3);
+        assert_eq!(pf.checked_product(), None);
+        assert_eq!(factor(u64::MAX).checked_product(), Some(u64::MAX));
+        assert_eq!((factor(u64::MAX) * factor(2)).checked_product(), None);
+        assert_eq!(factor(3).pow(40).checked_product(), Some(3_u64.pow(40)));
+        assert_eq!(factor(3).pow(41).checked_product(), None);
+    }
+
+    #[test]
+    fn u128_products() {
+        for a in (1..u64::MAX).step_by(0x0123_4567_89ab_cdef).take(20) {
+            for b in [1, 2, 1_000_003, u64::MAX - 58, u64::MAX] {
+                let pf = factor(a) * factor(b);
+                assert_eq!(pf.product_u128(), Some(u128::from(a) * u128::from(b)));
+            }
+        }
+        assert_eq!(factor(1 << 32).pow(3).product_u128(), Some(1 << 96));
+        assert_eq!(factor(2).pow(127).product_u128(), Some(1 << 127));
+        assert_eq!(factor(2).pow(128).product_u128(), None);
+        assert_eq!(factor(6).pow(49).product_u128(), Some(6_u128.pow(49)));
+        assert_eq!(factor(6).pow(50).product_u128(), None);
+        assert_eq!(factor(6).pow(50).checked_product(), None);
+        assert_eq!((factor(u64::MAX).pow(2) * factor(2)).product_u128(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn product_overflow() {
+        factor(1 << 32).pow(2).product();
+    }
+
+    #[test]
+    fn inline_storage() {
+        // 2·3·5·…·47 has the most distinct prime factors of any u64
+        let pf = factor(614_889_782_588_491_410);
+        assert_eq!(pf.iter().count(), 15);
+        assert!(!pf.facs.spilled());
+        assert_eq!(pf.product(), 614_889_782_588_491_410);
+        let big = &pf * &factor(53 * 59);
+        assert!(big.facs.spilled());
+        assert_eq!(big.iter().map(|(p, _)| p.get()).last(), Some(59));
+        assert_eq!(big.try_div(&factor(53 * 59)), Some(pf));
+    }
+
+    #[test]
+    fn hash_canonical() {
+        use std::collections::HashSet;
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{ Hash, Hasher };
+        fn hash(pf: &PrimeFactorization) -> u64 {
+            let mut h = DefaultHasher::new();
+            pf.hash(&mut h);
+            h.finish()
+        }
+        // reach the same numbers by different routes, which mustn't leave zero exponents behind
+        let p = |n| Prime::new(n).unwrap();
+        let mut built: PrimeFactorization = vec![(p(2), 0), (p(3), 2), (p(5), 0)].into_iter().collect();
+        assert_eq!(hash(&built), hash(&factor(9)));
+        built *= factor(10);
+        assert_eq!(built.try_div(&factor(10)).unwrap(), factor(9));
+        assert_eq!(hash(&built.try_div(&factor(10)).unwrap()), hash(&factor(9)));
+        assert_eq!(hash(&factor(12).gcd(&factor(35))), hash(&factor(1)));
+        assert_eq!(hash(&factor(6).pow(0)), hash(&factor(1)));
+        let set: HashSet<PrimeFactorization> = (1..1000).map(|n| factor(n).try_div(&factor(1)).unwrap()).collect();
+        assert_eq!(set.len(), 999);
+        assert!(set.contains(&(factor(20) * factor(30))));
     }
 
     #[test]
@@ -403,4 +2270,56 @@ mod tests {
             test_divisors(i);
         }
     }
+
+    #[test]
+    fn lazy_divisors() {
+        let n = 614_889_782_588_491_410;
+        let pf = factor(n);
+        let mut divs = pf.divisors();
+        assert_eq!(divs.by_ref().take(4).collect::<Vec<u64>>(), vec![1, 2, 3, 6]);
+        assert_eq!(divs.count(), (1 << 15) - 4);
+        assert!(factor(n).divisors().all(|d| n.is_multiple_of(d)));
+        assert_eq!(factor(1).divisors().collect::<Vec<u64>>(), vec![1]);
+        assert_eq!(factor(1 << 63).divisors().last(), Some(1 << 63));
+        assert_eq!(factor(u64::MAX).divisors().max(), Some(u64::MAX));
+        let seven = factor(7);
+        let mut done = seven.divisors();
+        assert_eq!(done.by_ref().count(), 2);
+        assert_eq!(done.next(), None);
+    }
+
+    #[test]
+    fn sorted_divisors() {
+        let n = 614_889_782_588_491_410;
+        let pf = factor(n);
+        let mut expected: Vec<u64> = pf.divisors().collect();
+        expected.sort_unstable();
+        assert_eq!(pf.divisors_sorted().collect::<Vec<u64>>(), expected);
+        for n in [1 << 63, u64::MAX, MAX_U64_PRIME, 963_761_198_400, 2 * 3 * 3 * 5 * 5 * 5 * 7 * 7] {
+            let pf = factor(n);
+            let mut expected: Vec<u64> = pf.divisors().collect();
+            expected.sort_unstable();
+            assert_eq!(pf.divisors_sorted().collect::<Vec<u64>>(), expected, "{}", n);
+        }
+        let seven = factor(7);
+        let mut divs = seven.divisors_sorted();
+        assert_eq!(divs.by_ref().count(), 2);
+        assert_eq!(divs.next(), None);
+    }
+
+    #[test]
+    fn large_divisor_pairs() {
+        assert_eq!(factor(u64::MAX).divisor_pairs().last(), Some((4_294_967_295, 4_294_967_297)));
+        assert_eq!(factor(MAX_U64_PRIME).divisor_pairs().collect::<Vec<_>>(), vec![(1, MAX_U64_PRIME)]);
+        assert_eq!(factor(1 << 63).divisor_pairs().count(), 32);
+        let square = 4_294_967_291 * 4_294_967_291;
+        assert_eq!(factor(square).divisor_pairs().last(), Some((4_294_967_291, 4_294_967_291)));
+        assert_eq!(factor(1).divisor_pairs().collect::<Vec<_>>(), vec![(1, 1)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn divisors_overflow() {
+        factor(1 << 32).pow(2).divisors().for_each(drop);
+    }
 }
@@ -0,0 +1,160 @@
+use super::*;
+
+use super::sieve::SegmentSieve;
+
+/// Produces the gaps between consecutive primes, as pairs `(p, q - p)` where `q` is the prime
+/// after `p`, in ascending order of `p`.
+///
+/// The iterator ends at `MAX_U64_PRIME`, which has no following prime in the u64 range.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::PrimeGaps;
+///
+/// fn main() {
+///     let gaps: Vec<(u64, u64)> = PrimeGaps::from(20).take(4).collect();
+///     assert_eq!(gaps, vec![(23, 6), (29, 2), (31, 6), (37, 4)]);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PrimeGaps {
+    primes: PrimeIter,
+    last: Option<u64>,
+}
+
+impl PrimeGaps {
+    /// Returns an iterator over the gaps following each prime at or above `n`.
+    pub fn from(n: u64) -> Self {
+        let mut primes = PrimeIter::from(n);
+        let last = primes.next();
+        PrimeGaps { primes, last }
+    }
+
+    /// Returns an iterator over the gaps following every u64 prime.
+    pub fn all() -> Self {
+        Self::from(2)
+    }
+}
+
+impl Iterator for PrimeGaps {
+    type Item = (u64, u64);
+    fn next(&mut self) -> Option<Self::Item> {
+        let p = self.last.filter(|&p| p < MAX_U64_PRIME)?;
+        let q = self.primes.next().unwrap();
+        self.last = Some(q);
+        Some((p, q - p))
+    }
+}
+
+impl std::iter::FusedIterator for PrimeGaps {}
+
+/// Finds the largest gap between consecutive primes below `x`, as `(p, gap)` for the first prime
+/// `p` where it occurs.
+///
+/// Both `p` and `p + gap` are less than `x`.  The primes are found with a segmented sieve, so
+/// this is much faster than walking `PrimeGaps`, at a cost of `O(x)` time.  Returns `None` when
+/// there are fewer than two primes below `x`.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::max_gap_below;
+///
+/// fn main() {
+///     assert_eq!(max_gap_below(1000), Some((887, 20)));
+///     assert_eq!(max_gap_below(120), Some((89, 8)));
+/// }
+/// ```
+pub fn max_gap_below(x: u64) -> Option<(u64, u64)> {
+    if x < 4 {
+        return None;
+    }
+    let hi = x - 1;
+    let mut last: Option<u64> = None;
+    let mut best: Option<(u64, u64)> = None;
+    SegmentSieve::new(hi).for_segments(2, hi, |primes| {
+        for &q in primes {
+            if let Some(p) = last {
+                if best.is_none_or(|(_, g)| q - p > g) {
+                    best = Some((p, q - p));
+                }
+            }
+            last = Some(q);
+        }
+        true
+    });
+    best
+}
+
+/// Finds the largest gap between consecutive primes below `x`, as the certified primes on either
+/// side and the gap between them.
+///
+/// This is `max_gap_below()` with typed results, so it sieves the same way.  In a release build
+/// 10^10 takes about half a minute, so checking the maximal gaps up to 10^12 is an hour-long
+/// batch job rather than a weeks-long one.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::max_prime_gap_below;
+///
+/// fn main() {
+///     let (p, q, gap) = max_prime_gap_below(1000).unwrap();
+///     assert_eq!((p.get(), q.get(), gap), (887, 907, 20));
+/// }
+/// ```
+pub fn max_prime_gap_below(x: u64) -> Option<(Prime, Prime, u64)> {
+    let (p, gap) = max_gap_below(x)?;
+    // this is safe because the sieve found both primes.
+    unsafe { Some((Prime::new_unsafe(p), Prime::new_unsafe(p + gap), gap)) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_gaps() {
+        let primes: Vec<u64> = PrimeIter::all().take_while(|&p| p < 100_000).collect();
+        let expected: Vec<(u64, u64)> = primes.windows(2).map(|w| (w[0], w[1] - w[0])).collect();
+        let got: Vec<(u64, u64)> = PrimeGaps::all().take(expected.len()).collect();
+        assert_eq!(got, expected);
+        for n in 0..1000 {
+            let first = expected.iter().copied().find(|&(p, _)| p >= n);
+            assert_eq!(PrimeGaps::from(n).next(), first);
+        }
+    }
+
+    #[test]
+    fn gaps_end() {
+        let last = PrimeGaps::from(u64::MAX - 1000).last().unwrap();
+        assert_eq!(last.0 + last.1, MAX_U64_PRIME);
+        assert_eq!(PrimeGaps::from(MAX_U64_PRIME).next(), None);
+        assert_eq!(PrimeGaps::from(u64::MAX).next(), None);
+    }
+
+    #[test]
+    fn compare_max_gaps() {
+        let mut best = None;
+        let mut gaps = PrimeGaps::all().peekable();
+        for x in 0..20_000 {
+            while let Some(&(p, g)) = gaps.peek() {
+                if p + g >= x {
+                    break;
+                }
+                if best.is_none_or(|(_, b)| g > b) {
+                    best = Some((p, g));
+                }
+                gaps.next();
+            }
+            assert_eq!(max_gap_below(x), best, "below {}", x);
+        }
+        // OEIS A002386 / A005250: maximal gaps
+        assert_eq!(max_gap_below(1_000_000), Some((492_113, 114)));
+        assert_eq!(max_gap_below(10_000_000), Some((4_652_353, 154)));
+        let (p, q, gap) = max_prime_gap_below(10_000_000).unwrap();
+        assert_eq!((p.get(), q.get(), gap), (4_652_353, 4_652_507, 154));
+        assert!(max_prime_gap_below(3).is_none());
+    }
+}
@@ -0,0 +1,135 @@
+use super::*;
+
+/// Finds the highly composite numbers up to and including `limit`, in ascending order.
+///
+/// A number is highly composite when it has more divisors than every smaller positive number.
+/// Every such number has non-increasing exponents over the consecutive primes 2, 3, 5, ..., so
+/// only those products are searched, with the primes taken from `CertIter`.  Use
+/// `divisor_count()` to get their divisor counts.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::highly_composite_numbers;
+///
+/// fn main() {
+///     assert_eq!(highly_composite_numbers(100), vec![1, 2, 4, 6, 12, 24, 36, 48, 60]);
+/// }
+/// ```
+pub fn highly_composite_numbers(limit: u64) -> Vec<u64> {
+    // Products of more than 15 primes don't fit in a u64.
+    let primes: Vec<u64> = CertIter::all().take(15).map(|p| p.get()).collect();
+
+    fn search(limit: u64, primes: &[u64], max_pow: u64, n: u64, d: u64, out: &mut Vec<(u64, u64)>) {
+        out.push((n, d));
+        if let Some((&p, rest)) = primes.split_first() {
+            let mut m = n;
+            for pow in 1..=max_pow {
+                m = match m.checked_mul(p) {
+                    Some(m) if m <= limit => m,
+                    _ => break,
+                };
+                search(limit, rest, pow, m, d * (pow + 1), out);
+            }
+        }
+    }
+    let mut candidates = Vec::new();
+    if limit >= 1 {
+        search(limit, &primes, u64::MAX, 1, 1, &mut candidates);
+    }
+    candidates.sort_unstable();
+    let mut res = Vec::new();
+    let mut best = 0;
+    for (n, d) in candidates {
+        if d > best {
+            best = d;
+            res.push(n);
+        }
+    }
+    res
+}
+
+/// Finds the superior highly composite numbers up to and including `limit`, in ascending order.
+///
+/// A number `n` is superior highly composite when some `ε > 0` makes `d(n) / n^ε` at least as
+/// large as `d(k) / k^ε` for every `k`.  For a given ε the best exponent of each prime `p` is
+/// `floor(1 / (p^ε - 1))`, so the sequence is built by sorting the critical values
+/// `log(1 + 1/k) / log(p)`, where the exponent of `p` grows from `k - 1` to `k`, and multiplying
+/// in one prime at a time as ε decreases.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::superior_highly_composite_numbers;
+///
+/// fn main() {
+///     assert_eq!(superior_highly_composite_numbers(10_000), vec![2, 6, 12, 60, 120, 360, 2520, 5040]);
+/// }
+/// ```
+pub fn superior_highly_composite_numbers(limit: u64) -> Vec<u64> {
+    let mut critical: Vec<(f64, u64)> = Vec::new();
+    for p in CertIter::all().take(15) {
+        let p = p.get();
+        for k in 1..64 {
+            critical.push(((1.0 + 1.0 / k as f64).ln() / (p as f64).ln(), p));
+        }
+    }
+    critical.sort_unstable_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+    let mut res = Vec::new();
+    let mut n: u64 = 1;
+    for (_, p) in critical {
+        n = match n.checked_mul(p) {
+            Some(m) if m <= limit => m,
+            _ => break,
+        };
+        res.push(n);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_highly_composite() {
+        let limit = 200_000;
+        let mut expected = Vec::new();
+        let mut best = 0;
+        for n in 1..=limit {
+            let d = divisor_count(n);
+            if d > best {
+                best = d;
+                expected.push(n);
+            }
+        }
+        assert_eq!(highly_composite_numbers(limit), expected);
+        assert!(highly_composite_numbers(0).is_empty());
+        assert_eq!(highly_composite_numbers(1), vec![1]);
+    }
+
+    #[test]
+    fn big_highly_composite() {
+        let hcn = highly_composite_numbers(u64::MAX);
+        // OEIS A002182: the largest below 2^64 has 184320 divisors
+        let last = *hcn.last().unwrap();
+        assert_eq!(last, 18_401_055_938_125_660_800);
+        assert_eq!(divisor_count(last), 184_320);
+        for w in hcn.windows(2) {
+            assert!(divisor_count(w[0]) < divisor_count(w[1]));
+        }
+    }
+
+    #[test]
+    fn superior_highly_composite() {
+        // OEIS A002201
+        assert_eq!(superior_highly_composite_numbers(1_000_000_000),
+                   vec![2, 6, 12, 60, 120, 360, 2520, 5040, 55440, 720720, 1441440, 4324320,
+                        21621600, 367567200]);
+        let shcn = superior_highly_composite_numbers(u64::MAX);
+        let hcn = highly_composite_numbers(u64::MAX);
+        for n in shcn {
+            assert!(hcn.binary_search(&n).is_ok(), "{} is not highly composite", n);
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use super::*;
+
+/// Finds every `n` with φ(n) = `m`.
+///
+/// Any such `n` is built from primes `p` with `p - 1` dividing `m`.  These candidates come from
+/// the divisors of `m`, and the search recursively chooses a power of each candidate prime whose
+/// totient still divides what is left of `m`.  Values of `n` that would not fit in a u64 are
+/// skipped.
+///
+/// The result is sorted in ascending order, and is empty when there are no solutions, as for
+/// every odd `m` above 1.
+pub fn totient_inverse(m: u64) -> Vec<u64> {
+    let mut res = Vec::new();
+    if m == 0 {
+        return res;
+    }
+    if m > 1 && m & 1 == 1 {
+        return res;
+    }
+    let mut primes = Vec::new();
+    factor(m).for_all_divisors(|d| {
+        if let Some(p) = d.checked_add(1).and_then(Prime::new) {
+            primes.push(p.get());
+        }
+    });
+    // Trying big primes first prunes the search much faster.
+    primes.sort_unstable_by(|a, b| b.cmp(a));
+
+    fn search(m: u64, primes: &[u64], acc: u64, res: &mut Vec<u64>) {
+        if m == 1 {
+            res.push(acc);
+        }
+        for (i, &p) in primes.iter().enumerate() {
+            if !m.is_multiple_of(p - 1) {
+                continue;
+            }
+            let mut rest = m / (p - 1);
+            let mut n = match acc.checked_mul(p) {
+                Some(n) => n,
+                None => continue,
+            };
+            loop {
+                search(rest, &primes[i + 1..], n, res);
+                if !rest.is_multiple_of(p) {
+                    break;
+                }
+                rest /= p;
+                n = match n.checked_mul(p) {
+                    Some(n) => n,
+                    None => break,
+                };
+            }
+        }
+    }
+    search(m, &primes, 1, &mut res);
+    res.sort_unstable();
+    res
+}
+
+/// Finds every `n` with σ(n) = `m`, where σ is the sum of divisors.
+///
+/// σ is multiplicative, so `n` is a product of prime powers `p^e` whose sums `σ(p^e)` are
+/// divisors of `m`.  Each divisor of `m` is checked for being such a sum, and the search then
+/// picks at most one power of each prime, keeping the product of their sums dividing `m`.
+///
+/// The result is sorted in ascending order.  Every preimage is smaller than `m`, so nothing is
+/// ever lost to overflow.
+pub fn sigma_inverse(m: u64) -> Vec<u64> {
+    use std::collections::BTreeMap;
+    let mut res = Vec::new();
+    if m == 0 {
+        return res;
+    }
+    // prime -> list of (p^e, σ(p^e)) with σ(p^e) dividing m
+    let mut powers: BTreeMap<u64, Vec<(u64, u64)>> = BTreeMap::new();
+    factor(m).for_all_divisors(|d| {
+        if d < 3 {
+            return;
+        }
+        if is_u64_prime(d - 1) {
+            powers.entry(d - 1).or_default().push((d - 1, d));
+        }
+        // p^e < σ(p^e) <= (p+1)^e, so p is the e-th root of d - 1.
+        let mut e = 2;
+        while e < 64 && (1_u64 << e) < d {
+            let p = nth_root(d - 1, e);
+            if p >= 2 && is_u64_prime(p) {
+                let pe = p.pow(e);
+                if (pe - 1) / (p - 1) + pe == d {
+                    powers.entry(p).or_default().push((pe, d));
+                }
+            }
+            e += 1;
+        }
+    });
+    let powers: Vec<Vec<(u64, u64)>> = powers.into_values().rev().collect();
+
+    fn search(m: u64, powers: &[Vec<(u64, u64)>], acc: u64, res: &mut Vec<u64>) {
+        if m == 1 {
+            res.push(acc);
+        }
+        for (i, choices) in powers.iter().enumerate() {
+            for &(pe, s) in choices.iter() {
+                if m.is_multiple_of(s) {
+                    search(m / s, &powers[i + 1..], acc * pe, res);
+                }
+            }
+        }
+    }
+    search(m, &powers, 1, &mut res);
+    res.sort_unstable();
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn small_totient_inverses() {
+        // φ(n) >= sqrt(n/2), so every preimage of m <= 300 is below 2 * 300^2.
+        let limit = 300;
+        let mut table: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+        for n in 1..=2 * limit * limit {
+            let t = euler_totient(n);
+            if t <= limit {
+                table.entry(t).or_default().push(n);
+            }
+        }
+        for m in 0..=limit {
+            let expected = table.remove(&m).unwrap_or_default();
+            assert_eq!(totient_inverse(m), expected, "totient_inverse({})", m);
+        }
+    }
+
+    #[test]
+    fn small_sigma_inverses() {
+        // σ(n) > n, so every preimage of m <= limit is below limit.
+        let limit = 20_000;
+        let mut table: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+        for n in 1..=limit {
+            let s = divisor_sum(n);
+            if s <= limit {
+                table.entry(s).or_default().push(n);
+            }
+        }
+        for m in 0..=limit {
+            let expected = table.remove(&m).unwrap_or_default();
+            assert_eq!(sigma_inverse(m), expected, "sigma_inverse({})", m);
+        }
+    }
+
+    #[test]
+    fn big_sigma_inverses() {
+        // σ(2^k * (2^(k+1) - 1)) = 2 * n for the perfect number with Mersenne prime 2^31 - 1.
+        let perfect = (1 << 30) * ((1 << 31) - 1);
+        assert!(sigma_inverse(2 * perfect).contains(&perfect));
+        let m = 1 << 40;
+        let ns = sigma_inverse(m);
+        assert!(!ns.is_empty());
+        for n in ns {
+            assert_eq!(divisor_sum(n), m);
+        }
+    }
+
+    #[test]
+    fn big_totient_inverses() {
+        let m = 1 << 32;
+        let ns = totient_inverse(m);
+        assert!(!ns.is_empty());
+        for n in ns {
+            assert_eq!(euler_totient(n), m);
+        }
+        // The preimages of p - 1 for the largest prime p include p itself and 2p overflows.
+        assert!(totient_inverse(MAX_U64_PRIME - 1).contains(&MAX_U64_PRIME));
+    }
+}
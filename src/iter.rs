@@ -1,19 +1,25 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::iter::FusedIterator;
+use std::ops::RangeBounds;
+use std::sync::OnceLock;
 
-use super::is_u64_prime;
+use super::{ is_u64_prime, nth_prime, prime_pi };
+use super::counting::prime_pi_bounds;
 use super::Prime;
+use super::sieve::{ inclusive_bounds, SegmentSieve, SEGMENT_LEN };
 
 /// PrimeIter returns a sequence of primes in ascending order.
 ///
-/// # Panics
-///
-/// This iterator will panic if it tries to generate a prime larger than `std::u64::MAX`.
-///
-/// To avoid panicking, use `Iterator::take_while()` or some other mechanism for limiting
-/// consumption.
+/// The sequence ends after `MAX_U64_PRIME`, the largest prime that fits in a u64.  Use
+/// `PrimeIter::range()` for an upper bound of your own.
 #[derive(Clone)]
 pub struct PrimeIter {
     last_output: u64, 
     next_jump: u64,
+    jumps: &'static [u8],
+    /// Exclusive upper bound, for iterators made by `range()`.
+    end: Option<u64>,
 }
 
 impl PrimeIter {
@@ -32,18 +38,97 @@ impl PrimeIter {
     /// ```
     ///
     pub fn from(n: u64) -> Self {
+        Self::from_with_wheel(n, Wheel::W210)
+    }
+
+    /// Like `PrimeIter::from()`, but skips candidates using the given wheel.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::{ PrimeIter, Wheel };
+    ///
+    /// fn main() {
+    ///     let ps: Vec<u64> = PrimeIter::from_with_wheel(30_000, Wheel::W30030).take(3).collect();
+    ///     assert_eq!(ps, vec![30011, 30013, 30029]);
+    /// }
+    /// ```
+    pub fn from_with_wheel(n: u64, wheel: Wheel) -> Self {
         let last_output = if is_u64_prime(n) {
             // safe since n >= 2
             n - 1
         } else {
             n
         };
-        let next_jump = if last_output < PrimeIter::PRIME_JUMPS.len() as u64 {
+        let jumps = wheel.jumps();
+        let next_jump = Self::jump(jumps, last_output);
+        PrimeIter { last_output, next_jump, jumps, end: None }
+    }
+
+    /// Captures the iterator's position, so that `from_state()` can resume it later, for instance
+    /// after a process restart.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::PrimeIter;
+    ///
+    /// fn main() {
+    ///     let mut ps = PrimeIter::range(100, 200);
+    ///     ps.nth(4);
+    ///     let state = ps.state();
+    ///     assert_eq!(state.after, 113);
+    ///     assert_eq!(PrimeIter::from_state(state).collect::<Vec<u64>>(), ps.collect::<Vec<u64>>());
+    /// }
+    /// ```
+    pub fn state(&self) -> PrimeIterState {
+        PrimeIterState {
+            after: self.last_output,
+            end: self.end,
+            wheel: Wheel::from_table_len(self.jumps.len()),
+        }
+    }
+
+    /// Returns an iterator that continues from a position captured by `state()`.
+    pub fn from_state(state: PrimeIterState) -> Self {
+        let jumps = state.wheel.jumps();
+        let next_jump = Self::jump(jumps, state.after);
+        PrimeIter { last_output: state.after, next_jump, jumps, end: state.end }
+    }
+
+    /// Moves the iterator forward so that it continues with the first prime on or after `n`.
+    ///
+    /// This doesn't visit the primes in between, and does nothing if the iterator is already past
+    /// `n`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::PrimeIter;
+    ///
+    /// fn main() {
+    ///     let mut ps = PrimeIter::all();
+    ///     ps.skip_to(1_000_000);
+    ///     assert_eq!(ps.next(), Some(1_000_003));
+    ///     ps.skip_to(10);
+    ///     assert_eq!(ps.next(), Some(1_000_033));
+    /// }
+    /// ```
+    pub fn skip_to(&mut self, n: u64) {
+        if n > self.last_output {
+            self.last_output = if is_u64_prime(n) { n - 1 } else { n };
+            self.next_jump = Self::jump(self.jumps, self.last_output);
+        }
+    }
+
+    /// The distance from `n` to the next candidate.  Below the wheel's modulus, the candidates
+    /// include the wheel's own primes, so every number is tried.
+    fn jump(jumps: &[u8], n: u64) -> u64 {
+        if n < jumps.len() as u64 {
             1
         } else {
-            PrimeIter::PRIME_JUMPS[(n % (PrimeIter::PRIME_JUMPS.len() as u64)) as usize]
-        } as u64;
-        PrimeIter { last_output, next_jump }
+            jumps[(n % jumps.len() as u64) as usize] as u64
+        }
     }
 
     /// Returns an iterator that generates all u64 primes in ascending order.
@@ -51,6 +136,73 @@ impl PrimeIter {
     pub fn all() -> Self {
         Self::from(2)
     }
+
+    /// Returns an iterator that generates the primes in `a..b` in ascending order.
+    ///
+    /// Its `size_hint()` gives an upper bound on the number of primes left.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::PrimeIter;
+    ///
+    /// fn main() {
+    ///     let ps: Vec<u64> = PrimeIter::range(10, 30).collect();
+    ///     assert_eq!(ps, vec![11, 13, 17, 19, 23, 29]);
+    ///     assert_eq!(PrimeIter::range(u64::MAX - 100, u64::MAX).count(), 3);
+    /// }
+    /// ```
+    pub fn range(a: u64, b: u64) -> Self {
+        PrimeIter { end: Some(b), ..Self::from(a) }
+    }
+
+    /// Returns an iterator over the primes `p ≡ a (mod d)` with `p >= start`, in ascending order.
+    ///
+    /// When `gcd(a, d) > 1` every term of the progression shares that factor, so at most one
+    /// prime can match and no candidates are walked.  See `ProgressionIter` for details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::PrimeIter;
+    ///
+    /// fn main() {
+    ///     let ps: Vec<u64> = PrimeIter::in_progression(3, 10, 0).take(5).collect();
+    ///     assert_eq!(ps, vec![3, 13, 23, 43, 53]);
+    ///     let ps: Vec<u64> = PrimeIter::in_progression(2, 4, 0).collect();
+    ///     assert_eq!(ps, vec![2]);
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `d` is 0.
+    pub fn in_progression(a: u64, d: u64, start: u64) -> ProgressionIter {
+        ProgressionIter::new(a, d, start)
+    }
+
+    /// Returns an iterator that generates the primes at or below `n` in descending order, ending
+    /// with 2.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::PrimeIter;
+    ///
+    /// fn main() {
+    ///     let ps: Vec<u64> = PrimeIter::descending_from(20).collect();
+    ///     assert_eq!(ps, vec![19, 17, 13, 11, 7, 5, 3, 2]);
+    /// }
+    /// ```
+    pub fn descending_from(n: u64) -> DescendingPrimeIter {
+        let last_output = if is_u64_prime(n) {
+            // safe since n <= MAX_U64_PRIME
+            n + 1
+        } else {
+            n
+        };
+        DescendingPrimeIter { last_output }
+    }
     // cargo test -- --nocapture dump_jumps
     // average jump len = 3.6952380952380954
     const PRIME_JUMPS: [u8; 210] = [1, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 2, 1, 4, 3, 2, 1, 2, 1, 4, 3,
@@ -60,7 +212,233 @@ impl PrimeIter {
         4, 3, 2, 1, 6, 5, 4, 3, 2, 1, 4, 3, 2, 1, 6, 5, 4, 3, 2, 1, 2, 1, 4, 3, 2, 1, 6, 5, 4, 3, 2, 1,
         2, 1, 6, 5, 4, 3, 2, 1, 6, 5, 4, 3, 2, 1, 4, 3, 2, 1, 2, 1, 4, 3, 2, 1, 6, 5, 4, 3, 2, 1, 2, 1,
         6, 5, 4, 3, 2, 1, 4, 3, 2, 1, 2, 1, 4, 3, 2, 1, 2, 1, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 2];
+    // the distance down to the previous number coprime to 210, also from dump_jumps
+    const PRIME_JUMPS_DOWN: [u8; 210] = [1, 2, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 1, 2, 1, 2, 3, 4, 1,
+        2, 1, 2, 3, 4, 1, 2, 3, 4, 5, 6, 1, 2, 1, 2, 3, 4, 5, 6, 1, 2, 3, 4, 1, 2, 1, 2, 3, 4, 1, 2,
+        3, 4, 5, 6, 1, 2, 3, 4, 5, 6, 1, 2, 1, 2, 3, 4, 5, 6, 1, 2, 3, 4, 1, 2, 1, 2, 3, 4, 5, 6, 1,
+        2, 3, 4, 1, 2, 3, 4, 5, 6, 1, 2, 3, 4, 5, 6, 7, 8, 1, 2, 3, 4, 1, 2, 1, 2, 3, 4, 1, 2, 1, 2,
+        3, 4, 1, 2, 3, 4, 5, 6, 7, 8, 1, 2, 3, 4, 5, 6, 1, 2, 3, 4, 1, 2, 3, 4, 5, 6, 1, 2, 1, 2, 3,
+        4, 1, 2, 3, 4, 5, 6, 1, 2, 1, 2, 3, 4, 5, 6, 1, 2, 3, 4, 5, 6, 1, 2, 3, 4, 1, 2, 1, 2, 3, 4,
+        1, 2, 3, 4, 5, 6, 1, 2, 1, 2, 3, 4, 5, 6, 1, 2, 3, 4, 1, 2, 1, 2, 3, 4, 1, 2, 1, 2, 3, 4, 5,
+        6, 7, 8, 9, 10];
+
+}
+
+/// The position of a `PrimeIter`, from `PrimeIter::state()`.
+///
+/// This is plain data, and with the `serde` feature it can be serialized, so a long enumeration
+/// can be checkpointed and resumed with `PrimeIter::from_state()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrimeIterState {
+    /// Every prime up to and including this has already been produced or skipped.
+    pub after: u64,
+    /// The exclusive upper bound of an iterator from `PrimeIter::range()`.
+    pub end: Option<u64>,
+    /// The wheel used to skip candidates.
+    pub wheel: Wheel,
+}
+
+/// The wheels `PrimeIter` can use to skip candidates with small prime factors.
+///
+/// A wheel is the product of the first few primes, and only candidates coprime to it are tested
+/// for primality.  Larger wheels test fewer candidates: 22.9% of all numbers for 210, 20.8% for
+/// 2310 and 19.2% for 30030.  The jump tables for the larger wheels are built the first time
+/// they are used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Wheel {
+    /// 2 * 3 * 5 * 7, the default.
+    W210,
+    /// 2 * 3 * 5 * 7 * 11.
+    W2310,
+    /// 2 * 3 * 5 * 7 * 11 * 13.
+    W30030,
+}
+
+impl Wheel {
+    /// Returns the product of the wheel's primes.
+    pub fn modulus(self) -> u64 {
+        match self {
+            Wheel::W210 => 210,
+            Wheel::W2310 => 2310,
+            Wheel::W30030 => 30030,
+        }
+    }
+
+    /// The wheel whose jump table has `len` entries.
+    fn from_table_len(len: usize) -> Wheel {
+        match len {
+            210 => Wheel::W210,
+            2310 => Wheel::W2310,
+            _ => Wheel::W30030,
+        }
+    }
+
+    /// Returns the jump table, where entry `i` is the distance from `i` to the next number
+    /// coprime to the modulus.
+    fn jumps(self) -> &'static [u8] {
+        static W2310: OnceLock<Vec<u8>> = OnceLock::new();
+        static W30030: OnceLock<Vec<u8>> = OnceLock::new();
+        match self {
+            Wheel::W210 => &PrimeIter::PRIME_JUMPS,
+            Wheel::W2310 => W2310.get_or_init(|| build_jumps(2310)),
+            Wheel::W30030 => W30030.get_or_init(|| build_jumps(30030)),
+        }
+    }
+}
+
+fn build_jumps(modulus: u64) -> Vec<u8> {
+    use num::Integer;
+    (0..modulus)
+        .map(|i| (1..).find(|&j| (i + j).gcd(&modulus) == 1).unwrap() as u8)
+        .collect()
+}
+
+/// DescendingPrimeIter returns a sequence of primes in descending order, ending with 2.  Create
+/// one with `PrimeIter::descending_from()`.
+///
+/// Like `PrimeIter`, it skips candidates sharing a factor with 210 using a jump table.
+#[derive(Clone, Debug)]
+pub struct DescendingPrimeIter {
+    last_output: u64,
+}
+
+impl Iterator for DescendingPrimeIter {
+    type Item = u64;
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = PrimeIter::PRIME_JUMPS_DOWN.len() as u64;
+        while self.last_output > 2 {
+            // Below 211 the jumps could skip 2, 3, 5 or 7, so walk one at a time.
+            let jump = if self.last_output <= len {
+                1
+            } else {
+                PrimeIter::PRIME_JUMPS_DOWN[(self.last_output % len) as usize] as u64
+            };
+            self.last_output -= jump;
+            if is_u64_prime(self.last_output) {
+                return Some(self.last_output);
+            }
+        }
+        None
+    }
+}
 
+impl FusedIterator for DescendingPrimeIter {}
+
+/// SievedPrimeIter returns the same sequence as `PrimeIter`, but finds the primes by sieving a
+/// segment of numbers at a time and handing them out from a buffer.
+///
+/// Below 2^40 no Miller-Rabin tests are needed at all, and above that only the sieve survivors
+/// are tested.  The table of sieving primes is grown as the segments climb, and each segment is
+/// sieved in full, so this pays off when enumerating many consecutive primes, while `PrimeIter`
+/// is better for taking just a few.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::SievedPrimeIter;
+///
+/// fn main() {
+///     let sum: u64 = SievedPrimeIter::all().take_while(|&p| p < 2_000_000).sum();
+///     assert_eq!(sum, 142_913_828_922);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct SievedPrimeIter {
+    sieve: SegmentSieve,
+    /// The largest number `sieve` was built for.
+    limit: u64,
+    buf: Vec<u64>,
+    pos: usize,
+    /// Start of the next segment, or None once the u64 range is used up.
+    next_lo: Option<u64>,
+}
+
+impl SievedPrimeIter {
+    /// Returns an iterator that generates all u64 primes in ascending order starting at the first
+    /// on or after `n`.
+    pub fn from(n: u64) -> Self {
+        let limit = n.saturating_add(SEGMENT_LEN - 1);
+        let sieve = SegmentSieve::new(limit);
+        SievedPrimeIter { sieve, limit, buf: Vec::new(), pos: 0, next_lo: Some(n) }
+    }
+
+    /// Returns an iterator that generates all u64 primes in ascending order.
+    pub fn all() -> Self {
+        Self::from(2)
+    }
+}
+
+impl Iterator for SievedPrimeIter {
+    type Item = u64;
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos == self.buf.len() {
+            let lo = self.next_lo?;
+            let hi = lo.saturating_add(SEGMENT_LEN - 1);
+            if hi > self.limit {
+                // growing the limit geometrically keeps the total rebuilding cost small
+                self.limit = hi.saturating_mul(16);
+                self.sieve = SegmentSieve::new(self.limit);
+            }
+            self.buf.clear();
+            self.pos = 0;
+            self.sieve.primes_in(lo, hi, &mut self.buf);
+            self.next_lo = hi.checked_add(1);
+        }
+        self.pos += 1;
+        Some(self.buf[self.pos - 1])
+    }
+}
+
+impl FusedIterator for SievedPrimeIter {}
+
+/// Calls `f` with the primes in a range, in ascending order, in chunks of `chunk_size`.
+///
+/// The range is sieved one segment at a time like `SievedPrimeIter`, and the primes are handed
+/// over in a reused buffer, so there is no per-prime iterator overhead.  Every chunk holds
+/// exactly `chunk_size` primes except possibly the last.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::for_primes_in;
+///
+/// fn main() {
+///     let mut chunks = Vec::new();
+///     for_primes_in(10..50, 4, |chunk| {
+///         chunks.push(chunk.iter().map(|p| p.get()).collect::<Vec<u64>>());
+///     });
+///     assert_eq!(chunks, vec![vec![11, 13, 17, 19], vec![23, 29, 31, 37], vec![41, 43, 47]]);
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `chunk_size` is 0.
+pub fn for_primes_in<R, F>(range: R, chunk_size: usize, mut f: F)
+    where R: RangeBounds<u64>, F: FnMut(&[Prime])
+{
+    assert!(chunk_size > 0, "for_primes_in called with chunk_size 0");
+    let (lo, hi) = match inclusive_bounds(&range) {
+        Some(b) => b,
+        None => return,
+    };
+    let mut chunk = Vec::with_capacity(chunk_size);
+    SegmentSieve::new(hi).for_segments(lo, hi, |primes| {
+        for &p in primes {
+            // this is safe because the sieve only outputs primes.
+            chunk.push(unsafe { Prime::new_unsafe(p) });
+            if chunk.len() == chunk_size {
+                f(&chunk);
+                chunk.clear();
+            }
+        }
+        true
+    });
+    if !chunk.is_empty() {
+        f(&chunk);
+    }
 }
 
 /// Produces a sequence of certified primes.
@@ -68,9 +446,9 @@ impl PrimeIter {
 /// Cost and behavior should be identical to PrimeIter, since it is a zero-cost wrapper around
 /// PrimeIter.
 ///
-/// CertIter will panic if it tries to produce a value larger than MAX_u64_PRIME, the same as
-/// PrimeIter.
+/// CertIter ends after MAX_U64_PRIME, the same as PrimeIter.
 ///
+#[derive(Clone, Debug)]
 pub struct CertIter {
     pi: PrimeIter,
 }
@@ -84,6 +462,46 @@ impl CertIter {
     pub fn from(n: u64) -> Self {
         Self::from_pi(PrimeIter::from(n))
     }
+    /// Returns a CertIter that produces all u64 primes at or above `n`, using the given wheel.
+    pub fn from_with_wheel(n: u64, wheel: Wheel) -> Self {
+        Self::from_pi(PrimeIter::from_with_wheel(n, wheel))
+    }
+    /// Captures the iterator's position, like `PrimeIter::state()`.
+    pub fn state(&self) -> PrimeIterState {
+        self.pi.state()
+    }
+    /// Returns a CertIter that continues from a position captured by `state()`.
+    pub fn from_state(state: PrimeIterState) -> Self {
+        Self::from_pi(PrimeIter::from_state(state))
+    }
+    /// Returns an iterator over `(i, p)` pairs, where `p` is the `i`-th prime, starting with the
+    /// `k`-th.
+    ///
+    /// Primes are counted from 2 as the first, like `nth_prime()`, which is used to find the
+    /// starting point, so `k = 0` is treated as 1.  The iterator is empty if there are fewer than
+    /// `k` primes in the u64 range.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use red_primality::CertIter;
+    ///
+    /// fn main() {
+    ///     let ps: Vec<(u64, u64)> = CertIter::enumerate_from(1_000_000).take(3)
+    ///         .map(|(i, p)| (i, p.get()))
+    ///         .collect();
+    ///     assert_eq!(ps, vec![(1_000_000, 15_485_863), (1_000_001, 15_485_867),
+    ///                         (1_000_002, 15_485_917)]);
+    /// }
+    /// ```
+    pub fn enumerate_from(k: u64) -> EnumeratedPrimes {
+        let index = k.max(1);
+        let primes = match nth_prime(index) {
+            Some(p) => CertIter::from(p),
+            None => CertIter::from_pi(PrimeIter::range(0, 0)),
+        };
+        EnumeratedPrimes { index, primes }
+    }
     /// Turns a PrimeIter into a CertIter.
     pub fn from_pi(pi: PrimeIter) -> Self {
         CertIter { pi }
@@ -103,8 +521,292 @@ impl Iterator for CertIter {
         // this is safe because the PrimeIter only outputs primes.
         self.pi.next().map(|n| unsafe { Prime::new_unsafe(n) })
     }
+
+    fn nth(&mut self, k: usize) -> Option<Self::Item> {
+        // this is safe because the PrimeIter only outputs primes.
+        self.pi.nth(k).map(|n| unsafe { Prime::new_unsafe(n) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.pi.size_hint()
+    }
+}
+
+impl FusedIterator for CertIter {}
+
+/// Adds primality filters to every iterator over u64.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::PrimalityIteratorExt;
+///
+/// fn main() {
+///     let ps: Vec<u64> = (90..110).filter_primes().map(|p| p.get()).collect();
+///     assert_eq!(ps, vec![97, 101, 103, 107, 109]);
+///     let cs: Vec<u64> = (0..10).filter_composites().collect();
+///     assert_eq!(cs, vec![4, 6, 8, 9]);
+/// }
+/// ```
+pub trait PrimalityIteratorExt: Iterator<Item = u64> + Sized {
+    /// Keeps only the primes, as certified `Prime` values.
+    fn filter_primes(self) -> FilterPrimes<Self> {
+        FilterPrimes { iter: self }
+    }
+
+    /// Keeps only the composite numbers.  0 and 1 are neither prime nor composite, so they are
+    /// dropped by both filters.
+    fn filter_composites(self) -> FilterComposites<Self> {
+        FilterComposites { iter: self }
+    }
+}
+
+impl<I: Iterator<Item = u64>> PrimalityIteratorExt for I {}
+
+/// An iterator that keeps only the primes from another iterator.  Create one with
+/// `PrimalityIteratorExt::filter_primes()`.
+#[derive(Clone, Debug)]
+pub struct FilterPrimes<I> {
+    iter: I,
+}
+
+impl<I: Iterator<Item = u64>> Iterator for FilterPrimes<I> {
+    type Item = Prime;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.by_ref().find_map(Prime::new)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+impl<I: FusedIterator<Item = u64>> FusedIterator for FilterPrimes<I> {}
+
+/// An iterator that keeps only the composite numbers from another iterator.  Create one with
+/// `PrimalityIteratorExt::filter_composites()`.
+#[derive(Clone, Debug)]
+pub struct FilterComposites<I> {
+    iter: I,
+}
+
+impl<I: Iterator<Item = u64>> Iterator for FilterComposites<I> {
+    type Item = u64;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.by_ref().find(|&n| n > 1 && !is_u64_prime(n))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+impl<I: FusedIterator<Item = u64>> FusedIterator for FilterComposites<I> {}
+
+/// Produces the primes along with their positions in the sequence of all primes.  Create one
+/// with `CertIter::enumerate_from()`.
+#[derive(Clone, Debug)]
+pub struct EnumeratedPrimes {
+    /// The index of the next prime `primes` will produce.
+    index: u64,
+    primes: CertIter,
+}
+
+impl Iterator for EnumeratedPrimes {
+    type Item = (u64, Prime);
+    fn next(&mut self) -> Option<Self::Item> {
+        let p = self.primes.next()?;
+        self.index += 1;
+        Some((self.index - 1, p))
+    }
+
+    fn nth(&mut self, k: usize) -> Option<Self::Item> {
+        let p = self.primes.nth(k)?;
+        self.index += k as u64 + 1;
+        Some((self.index - 1, p))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.primes.size_hint()
+    }
+}
+
+impl FusedIterator for EnumeratedPrimes {}
+
+/// The largest Sophie Germain prime candidate whose safe prime `2q + 1` fits in a u64.
+const MAX_SOPHIE_GERMAIN: u64 = (u64::MAX - 1) / 2;
+
+/// Odd primes used to reject Sophie Germain candidates before any Miller-Rabin test.
+const SOPHIE_GERMAIN_SIEVE: [u64; 13] = [5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47];
+
+/// Produces the Sophie Germain primes in ascending order: primes `q` for which `2q + 1` is also
+/// prime.
+///
+/// Above 3, both `q` and `2q + 1` are not divisible by 2 or 3 only when `q ≡ 5 (mod 6)`, so only
+/// those candidates are visited.  Each is checked against a few more small primes, for both `q`
+/// and `2q + 1` at once, before either gets a full primality test.
+///
+/// The iterator ends once `2q + 1` would no longer fit in a u64.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::SophieGermainIter;
+///
+/// fn main() {
+///     let sg: Vec<u64> = SophieGermainIter::from(0).take(8).map(|p| p.get()).collect();
+///     assert_eq!(sg, vec![2, 3, 5, 11, 23, 29, 41, 53]);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct SophieGermainIter {
+    next: Option<u64>,
+}
+
+impl SophieGermainIter {
+    /// Returns an iterator over the Sophie Germain primes at or above `n`.
+    pub fn from(n: u64) -> Self {
+        let next = if n <= 5 {
+            Some(n)
+        } else {
+            // the first candidate ≡ 5 (mod 6) at or above n
+            n.checked_add((11 - n % 6) % 6)
+        };
+        SophieGermainIter { next }
+    }
+
+    /// Returns an iterator over all the Sophie Germain primes whose safe primes fit in a u64.
+    pub fn all() -> Self {
+        Self::from(0)
+    }
+
+    /// Tests a candidate `q ≡ 5 (mod 6)`.
+    fn check(q: u64) -> bool {
+        let p = 2 * q + 1;
+        for &r in SOPHIE_GERMAIN_SIEVE.iter() {
+            if (q.is_multiple_of(r) && q != r) || (p.is_multiple_of(r) && p != r) {
+                return false;
+            }
+        }
+        is_u64_prime(q) && is_u64_prime(p)
+    }
+}
+
+impl Iterator for SophieGermainIter {
+    type Item = Prime;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let q = self.next.filter(|&q| q <= MAX_SOPHIE_GERMAIN)?;
+            if q < 5 {
+                self.next = Some(q + 1);
+                if is_u64_prime(q) && is_u64_prime(2 * q + 1) {
+                    return Prime::new(q);
+                }
+            } else {
+                self.next = q.checked_add(6);
+                if Self::check(q) {
+                    // this is safe because check() tested q for primality.
+                    return Some(unsafe { Prime::new_unsafe(q) });
+                }
+            }
+        }
+    }
+}
+
+impl FusedIterator for SophieGermainIter {}
+
+/// Produces the safe primes in ascending order: primes `p` for which `(p - 1) / 2` is also prime.
+///
+/// This walks the Sophie Germain primes `q` with `SophieGermainIter` and yields `2q + 1`.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::SafePrimeIter;
+///
+/// fn main() {
+///     let safe: Vec<u64> = SafePrimeIter::from(10).take(5).map(|p| p.get()).collect();
+///     assert_eq!(safe, vec![11, 23, 47, 59, 83]);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct SafePrimeIter {
+    sg: SophieGermainIter,
+}
+
+impl SafePrimeIter {
+    /// Returns an iterator over the safe primes at or above `n`.
+    pub fn from(n: u64) -> Self {
+        // 2q + 1 >= n exactly when q >= n / 2
+        SafePrimeIter { sg: SophieGermainIter::from(n / 2) }
+    }
+
+    /// Returns an iterator over all the u64 safe primes.
+    pub fn all() -> Self {
+        Self::from(0)
+    }
+}
+
+impl Iterator for SafePrimeIter {
+    type Item = Prime;
+    fn next(&mut self) -> Option<Self::Item> {
+        // this is safe because SophieGermainIter checks 2q + 1 for primality.
+        self.sg.next().map(|q| unsafe { Prime::new_unsafe(2 * q.get() + 1) })
+    }
+}
+
+impl FusedIterator for SafePrimeIter {}
+
+/// Produces the primes in the arithmetic progression `a, a + d, a + 2d, ...` in ascending order,
+/// starting at some lower bound.  Create one with `PrimeIter::in_progression()`.
+///
+/// If `gcd(a, d) = 1` every candidate `a + kd` is tested in turn.  Otherwise the only possible
+/// prime is `gcd(a, d)` itself, which is yielded if it lies in the progression.
+///
+/// Like `PrimeIter`, this ends when the next term would pass `u64::MAX`.
+#[derive(Clone, Debug)]
+pub struct ProgressionIter {
+    next: Option<u64>,
+    step: u64,
+}
+
+impl ProgressionIter {
+    fn new(a: u64, d: u64, start: u64) -> Self {
+        use num::Integer;
+        assert!(d != 0, "arithmetic progression with difference 0");
+        let r = a % d;
+        if r.gcd(&d) != 1 {
+            // p ≡ r (mod d) and gcd(r, d) | p, so p = gcd(r, d), which is r or, when r = 0, d.
+            let c = if r == 0 { d } else { r };
+            let next = Some(c).filter(|&c| c >= start && is_u64_prime(c));
+            return ProgressionIter { next, step: 0 };
+        }
+        // the first term at or above start, without forming r + d, which can overflow
+        let s = start % d;
+        let next = start.checked_add(if r >= s { r - s } else { d - (s - r) });
+        ProgressionIter { next, step: d }
+    }
+}
+
+impl Iterator for ProgressionIter {
+    type Item = u64;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let n = self.next?;
+            if self.step == 0 {
+                self.next = None;
+                return Some(n);
+            }
+            self.next = n.checked_add(self.step);
+            if is_u64_prime(n) {
+                return Some(n);
+            }
+        }
+    }
 }
 
+impl FusedIterator for ProgressionIter {}
+
 #[test]
 fn dump_jumps() {
     use num::Integer;
@@ -128,13 +830,17 @@ fn dump_jumps() {
         println!("average jump len = {}", tot_jump as f64 / (len as f64) * 2.0);
     }
     println!("const PRIME_JUMPS: [u8; {}] = {:?};", len, v);
+    let down: Vec<u64> = (0..len)
+        .map(|i| (1..30).find(|&j| (i + len - j).gcd(&len) == 1).unwrap())
+        .collect();
+    println!("const PRIME_JUMPS_DOWN: [u8; {}] = {:?};", len, down);
 }
 
 #[test]
 fn dump_end() {
-    for p in (std::u64::MAX - 1000)..=std::u64::MAX {
+    for p in (u64::MAX - 1000)..=u64::MAX {
         if is_u64_prime(p) {
-            println!("{} (2^64 - {}) is prime", p, std::u64::MAX - p + 1);
+            println!("{} (2^64 - {}) is prime", p, u64::MAX - p + 1);
         }
     }
     // results appear to match https://primes.utm.edu/lists/2small/0bit.html
@@ -142,26 +848,19 @@ fn dump_end() {
 
 
 #[test]
-#[should_panic]
 fn run_past_end() {
-    let start = std::u64::MAX - 1000;
-    let ps = PrimeIter::from(start);
-    let mut got_biggest = false;
-    // expect ps to panic when it tries to move past end
-    for p in ps {
-        println!("got {}", p);
-        if got_biggest {
-            println!("Should not have gotten a prime after {}", super::MAX_U64_PRIME);
-            return;  // this causes test failure because of [should_pannic]
-        }
-        if p == super::MAX_U64_PRIME {
-            got_biggest = true;
-        }
-    }
+    let start = u64::MAX - 1000;
+    let mut ps = PrimeIter::from(start);
+    // expect ps to end after the biggest prime, and stay ended
+    assert_eq!(ps.by_ref().last(), Some(super::MAX_U64_PRIME));
+    assert_eq!(ps.next(), None);
+    assert_eq!(ps.next(), None);
+    assert_eq!(PrimeIter::from(u64::MAX).next(), None);
+    assert_eq!(CertIter::from(super::MAX_U64_PRIME + 1).next(), None);
 }
 #[test]
 fn check_includes_biggest() {
-    let start = std::u64::MAX - 1000;
+    let start = u64::MAX - 1000;
     let ps = PrimeIter::from(start);
     for p in ps {
         if p == super::MAX_U64_PRIME {
@@ -175,26 +874,79 @@ impl Iterator for PrimeIter {
     type Item = u64;
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            // overflow panic should occur here in debug, after last valid prime returned
-            let next_output = self.last_output + self.next_jump;
-
-            // but in release we need to check manually.
-            if next_output < self.last_output {
-                panic!("PrimeIter has overflowed past std::u64::MAX");
+            // everything from here to u64::MAX is composite once this overflows
+            let next_output = self.last_output.checked_add(self.next_jump)?;
+            if self.end.is_some_and(|end| next_output >= end) {
+                return None;
             }
             self.last_output = next_output;
 
-            self.next_jump = 
-                if self.last_output < PrimeIter::PRIME_JUMPS.len() as u64 {
-                    1
-                } else {
-                    PrimeIter::PRIME_JUMPS[(self.last_output % (PrimeIter::PRIME_JUMPS.len() as u64)) as usize] as u64
-                };
+            self.next_jump = PrimeIter::jump(self.jumps, self.last_output);
             if is_u64_prime(self.last_output) {
                 return Some(self.last_output);
             }
         }
     }
+
+    /// Skips `k` primes and returns the next one.
+    ///
+    /// For large `k` this counts the primes consumed so far with `prime_pi()`, finds the target
+    /// with `nth_prime()`, and jumps straight there with `skip_to()`.  That costs two prime
+    /// counts instead of about `k ln(x)` candidate tests, so it is only done when it looks cheaper.
+    fn nth(&mut self, k: usize) -> Option<Self::Item> {
+        // don't count primes near the end just to find that there aren't k more
+        let rest = PrimeIter { end: Some(self.end.unwrap_or(u64::MAX)), ..self.clone() };
+        if rest.size_hint().1.is_some_and(|upper| k >= upper) {
+            self.skip_to(u64::MAX);
+            return None;
+        }
+        let x = self.last_output.max(2) as f64;
+        let walk_cost = k as f64 * x.ln() * 5.0;
+        let pi_cost = 10.0 * (x + k as f64 * x.ln()).powf(2.0 / 3.0);
+        if walk_cost > pi_cost {
+            // every prime up to last_output has been consumed or skipped
+            let index = prime_pi(self.last_output) + k as u64 + 1;
+            self.skip_to(nth_prime(index).unwrap_or(u64::MAX));
+            return self.next();
+        }
+        for _ in 0..k {
+            self.next()?;
+        }
+        self.next()
+    }
+
+    /// For iterators made by `range()`, bounds the number of primes left from both sides with
+    /// estimates of π(x) at either end.  Narrow ranges get a lower bound of 0, and the upper
+    /// bound is also limited by counting the candidates the wheel leaves.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.end {
+            Some(end) if end > 0 && end - 1 > self.last_output => {
+                let (lo_a, hi_a) = prime_pi_bounds(self.last_output);
+                let (lo_b, hi_b) = prime_pi_bounds(end - 1);
+                // Every prime above 7 is coprime to 210, like 48 of every 210 integers.
+                let width = end - self.last_output - 1;
+                let upper = (hi_b - lo_a).min((width / 210 + 1) * 48 + 4);
+                let lower = lo_b.saturating_sub(hi_a);
+                (usize::try_from(lower).unwrap_or(usize::MAX), usize::try_from(upper).ok())
+            }
+            Some(_) => (0, Some(0)),
+            None => (0, None),
+        }
+    }
+}
+
+impl FusedIterator for PrimeIter {}
+
+impl fmt::Debug for PrimeIter {
+    // The jump table is left out, since it can have 30030 entries.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PrimeIter")
+            .field("last_output", &self.last_output)
+            .field("next_jump", &self.next_jump)
+            .field("wheel", &self.jumps.len())
+            .field("end", &self.end)
+            .finish()
+    }
 }
 
 #[cfg(test)]
@@ -216,4 +968,265 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn compare_descending() {
+        let primes: Vec<u64> = PrimeIter::all().take_while(|&p| p < 100_000).collect();
+        let got: Vec<u64> = PrimeIter::descending_from(100_000).collect();
+        assert_eq!(got, primes.iter().rev().copied().collect::<Vec<u64>>());
+        for n in 0..1000 {
+            let first = primes.iter().rev().copied().find(|&p| p <= n);
+            assert_eq!(PrimeIter::descending_from(n).next(), first, "from {}", n);
+        }
+        let top: Vec<u64> = PrimeIter::descending_from(u64::MAX).take(21).collect();
+        let mut bottom: Vec<u64> = PrimeIter::from(u64::MAX - 1000).take(21).collect();
+        bottom.reverse();
+        assert_eq!(top, bottom);
+    }
+
+    #[test]
+    fn compare_ranges() {
+        let primes: Vec<u64> = PrimeIter::all().take_while(|&p| p < 2000).collect();
+        for a in 0..300 {
+            for b in (a..2000).step_by(37) {
+                let expected: Vec<u64> = primes.iter().copied().filter(|&p| a <= p && p < b).collect();
+                let mut it = PrimeIter::range(a, b);
+                let (lower, upper) = it.size_hint();
+                let upper = upper.unwrap();
+                assert!(lower <= expected.len() && expected.len() <= upper,
+                        "{}..{}: {} not in {}..={}", a, b, expected.len(), lower, upper);
+                assert_eq!(it.by_ref().collect::<Vec<u64>>(), expected, "{}..{}", a, b);
+                assert_eq!(it.next(), None);
+            }
+        }
+        assert_eq!(PrimeIter::range(u64::MAX - 1000, u64::MAX).count(), 21);
+        assert_eq!(PrimeIter::range(u64::MAX - 1000, u64::MAX).last(), Some(super::super::MAX_U64_PRIME));
+        assert_eq!(PrimeIter::range(100, 50).next(), None);
+        assert_eq!(PrimeIter::all().size_hint(), (0, None));
+        // size_hint() mustn't overflow once the iterator has reached the top of the range
+        let mut top = PrimeIter::range(u64::MAX, u64::MAX);
+        assert_eq!(top.size_hint(), (0, Some(0)));
+        assert_eq!(top.next(), None);
+        let mut skipped = PrimeIter::range(0, u64::MAX);
+        skipped.skip_to(u64::MAX);
+        assert_eq!(skipped.size_hint(), (0, Some(0)));
+        assert_eq!(skipped.next(), None);
+        assert_eq!(PrimeIter::range(0, 0).size_hint(), (0, Some(0)));
+        let (lower, upper) = PrimeIter::range(1_000_000, 100_000_000).size_hint();
+        let upper = upper.unwrap();
+        // 5_682_957 primes
+        assert!((5_000_000..=5_682_957).contains(&lower));
+        assert!((5_682_957..6_000_000).contains(&upper));
+    }
+
+    #[test]
+    fn compare_sieved() {
+        let expected: Vec<u64> = PrimeIter::all().take_while(|&p| p < 3_000_000).collect();
+        let got: Vec<u64> = SievedPrimeIter::all().take_while(|&p| p < 3_000_000).collect();
+        assert_eq!(got, expected);
+        for n in 0..1000 {
+            assert_eq!(SievedPrimeIter::from(n).next(), PrimeIter::from(n).next());
+        }
+        for &start in [1 << 40, 1 << 50, u64::MAX - 3_000_000].iter() {
+            let expected: Vec<u64> = PrimeIter::from(start).take(2000).collect();
+            let got: Vec<u64> = SievedPrimeIter::from(start).take(2000).collect();
+            assert_eq!(got, expected, "from {}", start);
+        }
+        let mut top = SievedPrimeIter::from(u64::MAX - 1000);
+        assert_eq!(top.by_ref().count(), 21);
+        assert_eq!(top.next(), None);
+    }
+
+    #[test]
+    fn compare_wheels() {
+        assert_eq!(build_jumps(210), PrimeIter::PRIME_JUMPS.to_vec());
+        let expected: Vec<u64> = PrimeIter::all().take_while(|&p| p < 300_000).collect();
+        for &wheel in [Wheel::W2310, Wheel::W30030].iter() {
+            let got: Vec<u64> = PrimeIter::from_with_wheel(0, wheel).take_while(|&p| p < 300_000).collect();
+            assert_eq!(got, expected, "{:?}", wheel);
+            for n in (0..100_000).step_by(97) {
+                assert_eq!(PrimeIter::from_with_wheel(n, wheel).next(), PrimeIter::from(n).next());
+            }
+            let top: Vec<u64> = CertIter::from_with_wheel(u64::MAX - 1000, wheel).map(|p| p.get()).collect();
+            assert_eq!(top, PrimeIter::from(u64::MAX - 1000).collect::<Vec<u64>>());
+        }
+    }
+
+    #[test]
+    fn compare_nth() {
+        let primes: Vec<u64> = PrimeIter::all().take_while(|&p| p < 3_000_000).collect();
+        for &start in [0, 2, 3, 1000, 7919, 1_000_000].iter() {
+            let first = primes.iter().position(|&p| p >= start).unwrap();
+            for &k in [0, 1, 10, 1000, 10_000, 100_000].iter() {
+                let mut ps = PrimeIter::from(start);
+                assert_eq!(ps.nth(k), Some(primes[first + k]), "{} from {}", k, start);
+                assert_eq!(ps.next(), Some(primes[first + k + 1]));
+            }
+        }
+        // the 10^9-th prime
+        assert_eq!(PrimeIter::all().nth(999_999_999), Some(22_801_763_489));
+        assert_eq!(PrimeIter::range(0, 1000).nth(100_000), None);
+        assert_eq!(PrimeIter::from(u64::MAX - 1000).nth(1 << 40), None);
+        let mut ps = PrimeIter::range(0, 1_000_000);
+        ps.skip_to(999_900);
+        let tail: Vec<u64> = primes.iter().copied().filter(|&p| (999_900..1_000_000).contains(&p)).collect();
+        assert_eq!(ps.collect::<Vec<u64>>(), tail);
+    }
+
+    #[test]
+    fn trait_coverage() {
+        fn check<I: Iterator + FusedIterator + Clone + fmt::Debug>(mut it: I) {
+            let copy = it.clone();
+            assert!(!format!("{:?}", copy).is_empty());
+            while it.next().is_some() {}
+            assert!(it.next().is_none() && it.next().is_none());
+        }
+        check(PrimeIter::range(0, 1000));
+        check(PrimeIter::from(u64::MAX - 1000));
+        check(PrimeIter::descending_from(1000));
+        check(PrimeIter::in_progression(1, 4, u64::MAX - 10_000));
+        check(SievedPrimeIter::from(u64::MAX - 1000));
+        check(CertIter::from_pi(PrimeIter::range(0, 1000)));
+        check(SophieGermainIter::from(u64::MAX / 2 - 100_000));
+        check(SafePrimeIter::from(u64::MAX - 100_000));
+        assert_eq!(format!("{:?}", PrimeIter::range(10, 20)),
+                   "PrimeIter { last_output: 10, next_jump: 1, wheel: 210, end: Some(20) }");
+    }
+
+    #[test]
+    fn compare_chunks() {
+        for &(a, b) in [(0, 0), (0, 3), (0, 3_000_000), (u64::MAX - 1000, u64::MAX)].iter() {
+            let expected: Vec<u64> = PrimeIter::range(a, b).collect();
+            for &size in [1, 7, 1000, 1 << 20].iter() {
+                let mut got = Vec::new();
+                let mut sizes = Vec::new();
+                for_primes_in(a..b, size, |chunk| {
+                    sizes.push(chunk.len());
+                    got.extend(chunk.iter().map(|p| p.get()));
+                });
+                assert_eq!(got, expected, "{}..{} by {}", a, b, size);
+                let full = sizes.len().saturating_sub(1);
+                assert!(sizes[..full].iter().all(|&n| n == size));
+                assert!(sizes.last().is_none_or(|&n| 0 < n && n <= size));
+            }
+        }
+    }
+
+    #[test]
+    fn compare_filters() {
+        let primes: Vec<u64> = PrimeIter::range(0, 100_000).collect();
+        let got: Vec<u64> = (0..100_000).filter_primes().map(|p| p.get()).collect();
+        assert_eq!(got, primes);
+        let composites: Vec<u64> = (2..100_000).filter(|n| primes.binary_search(n).is_err()).collect();
+        assert_eq!((0..100_000).filter_composites().collect::<Vec<u64>>(), composites);
+        let top: Vec<u64> = (u64::MAX - 1000..=u64::MAX).rev().filter_primes().map(|p| p.get()).collect();
+        assert_eq!(top, PrimeIter::descending_from(u64::MAX).take(21).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn resume_from_state() {
+        let expected: Vec<u64> = PrimeIter::range(0, 50_000).collect();
+        for &wheel in [Wheel::W210, Wheel::W2310, Wheel::W30030].iter() {
+            let mut ps = PrimeIter::from_with_wheel(0, wheel);
+            ps.end = Some(50_000);
+            let mut got = Vec::new();
+            for _ in 0..100 {
+                let state = ps.state();
+                assert_eq!(state.wheel, wheel);
+                ps = PrimeIter::from_state(state);
+                got.extend(ps.by_ref().take(97));
+            }
+            got.extend(ps);
+            assert_eq!(got, expected);
+        }
+        let mut cs = CertIter::from(u64::MAX - 1000);
+        cs.next();
+        let rest: Vec<Prime> = CertIter::from_state(cs.state()).collect();
+        assert_eq!(rest, cs.collect::<Vec<Prime>>());
+    }
+
+    #[test]
+    fn enumerated() {
+        let primes: Vec<u64> = PrimeIter::range(0, 100_000).collect();
+        for &k in [0, 1, 2, 10, 1000].iter() {
+            let got: Vec<(u64, u64)> = CertIter::enumerate_from(k).take(100)
+                .map(|(i, p)| (i, p.get()))
+                .collect();
+            let first = k.max(1);
+            let expected: Vec<(u64, u64)> = (first..first + 100)
+                .map(|i| (i, primes[i as usize - 1]))
+                .collect();
+            assert_eq!(got, expected, "from {}", k);
+        }
+        let mut it = CertIter::enumerate_from(5);
+        assert_eq!(it.nth(1000).map(|(i, p)| (i, p.get())), Some((1005, primes[1004])));
+        assert_eq!(it.next().map(|(i, p)| (i, p.get())), Some((1006, primes[1005])));
+        // there are 425_656_284_035_217_743 primes below 2^64
+        assert_eq!(CertIter::enumerate_from(425_656_284_035_217_744).next(), None);
+    }
+
+    #[test]
+    fn from_prime() {
+        for p in PrimeIter::all().take_while(|&p| p < 10_000) {
+            assert_eq!(PrimeIter::from(p).next(), Some(p));
+        }
+    }
+
+    #[test]
+    fn compare_sophie_germain() {
+        let expected: Vec<u64> = PrimeIter::all()
+            .take_while(|&q| q < LIMIT)
+            .filter(|&q| is_u64_prime(2 * q + 1))
+            .collect();
+        let sg: Vec<u64> = SophieGermainIter::all().map(|q| q.get()).take_while(|&q| q < LIMIT).collect();
+        assert_eq!(sg, expected);
+        let safe: Vec<u64> = SafePrimeIter::all().map(|p| p.get()).take_while(|&p| p < 2 * LIMIT).collect();
+        assert_eq!(safe, expected.iter().map(|q| 2 * q + 1).collect::<Vec<u64>>());
+        for n in 0..1000 {
+            let first = expected.iter().copied().find(|&q| q >= n);
+            assert_eq!(SophieGermainIter::from(n).next().map(|q| q.get()), first);
+            let first = expected.iter().map(|q| 2 * q + 1).find(|&p| p >= n);
+            assert_eq!(SafePrimeIter::from(n).next().map(|p| p.get()), first);
+        }
+    }
+
+    #[test]
+    fn compare_progressions() {
+        let limit = 10_000;
+        let primes: Vec<u64> = PrimeIter::all().take_while(|&p| p < limit).collect();
+        for d in 1..40 {
+            for a in 0..2 * d {
+                for &start in [0, 1, 2, 3, 100, 997].iter() {
+                    let expected: Vec<u64> = primes.iter().copied()
+                        .filter(|&p| p >= start && p % d == a % d)
+                        .collect();
+                    let got: Vec<u64> = PrimeIter::in_progression(a, d, start)
+                        .take_while(|&p| p < limit)
+                        .collect();
+                    assert_eq!(got, expected, "{} mod {} from {}", a, d, start);
+                }
+            }
+        }
+        let last: Vec<u64> = PrimeIter::in_progression(1, 2, u64::MAX - 1000).collect();
+        assert_eq!(last.len(), 21);
+        assert_eq!(*last.last().unwrap(), super::super::MAX_U64_PRIME);
+        // differences near u64::MAX leave room for at most one or two terms
+        let top = super::super::MAX_U64_PRIME;
+        assert_eq!(PrimeIter::in_progression(u64::MAX - 1, u64::MAX, 0).next(), None);
+        assert_eq!(PrimeIter::in_progression(1, u64::MAX, 0).next(), None);
+        assert_eq!(PrimeIter::in_progression(top, u64::MAX, 0).collect::<Vec<u64>>(), vec![top]);
+        assert_eq!(PrimeIter::in_progression(top, u64::MAX - 1, 3).collect::<Vec<u64>>(), vec![top]);
+        assert_eq!(PrimeIter::in_progression(2, u64::MAX - 1, 3).next(), None);
+        assert_eq!(PrimeIter::in_progression(3, u64::MAX, u64::MAX).next(), None);
+    }
+
+    #[test]
+    fn sophie_germain_end() {
+        let last: Vec<u64> = SafePrimeIter::from(u64::MAX - 1_000_000).map(|p| p.get()).collect();
+        assert!(!last.is_empty());
+        for &p in last.iter() {
+            assert!(is_u64_prime(p) && is_u64_prime((p - 1) / 2));
+        }
+        assert_eq!(SophieGermainIter::from(u64::MAX).next(), None);
+    }
 }
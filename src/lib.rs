@@ -3,7 +3,7 @@
 //! # Example
 //!
 //! ```
-//! use red_primality::{ is_u64_prime, factor, Prime, euler_totient, mobius };
+//! use red_primality::{ is_u64_prime, factor, Prime, euler_totient, mobius_n, mobius_ratio };
 //!
 //! fn main() {
 //!     // Primality testing
@@ -19,12 +19,23 @@
 //!     // Euler's totient function
 //!     assert_eq!(euler_totient(180), (1*2) * (2*3) * (4));
 //!
-//!     // mobius(x,y) is the Möbius function of the ratio x/y
-//!     assert_eq!(mobius(90, 2), 0);  // 90/2 = 45, contiains 3^2
-//!     assert_eq!(mobius(90, 3), -1);  // 90/3 = 30 = 2*3*5, so -1
-//!     assert_eq!(mobius(90, 6), 1);  // 90/6 = 15 = 3*5, so +1
+//!     // The Möbius function
+//!     assert_eq!(mobius_n(30), -1);  // 30 = 2*3*5, so -1
+//!
+//!     // mobius_ratio(x,y) is the Möbius function of the ratio x/y
+//!     assert_eq!(mobius_ratio(90, 2), 0);  // 90/2 = 45, contiains 3^2
+//!     assert_eq!(mobius_ratio(90, 3), -1);  // 90/3 = 30 = 2*3*5, so -1
+//!     assert_eq!(mobius_ratio(90, 6), 1);  // 90/6 = 15 = 3*5, so +1
 //! }
 //! ```
+//!
+//! # Features
+//!
+//! * `rand`: random prime generation, such as `random_prime_in()`, `random_prime_bits()` and
+//!   `RandomPrimeIter`, and the `crypto` module.
+//! * `parallel`: `par_primes_in()`, a rayon parallel iterator over the primes in a range.
+//! * `serde`: `Serialize` and `Deserialize` for `PrimeIterState` and `Wheel`, so prime
+//!   enumerations can be checkpointed.
 
 #![deny(missing_docs)]
 
@@ -36,3 +47,66 @@ pub use iter::*;
 
 mod factor;
 pub use factor::*;
+
+mod sieve;
+
+mod counting;
+pub use counting::*;
+
+mod gaps;
+pub use gaps::*;
+
+mod prime_set;
+pub use prime_set::*;
+
+mod combinatorics;
+pub use combinatorics::*;
+
+mod diophantine;
+pub use diophantine::*;
+
+mod highly_composite;
+pub use highly_composite::*;
+
+mod multiplicative;
+pub use multiplicative::*;
+
+mod dirichlet;
+pub use dirichlet::*;
+
+mod special;
+pub use special::*;
+
+mod recreational;
+pub use recreational::*;
+
+mod summatory;
+pub use summatory::*;
+
+mod aliquot;
+pub use aliquot::*;
+
+mod inverse;
+pub use inverse::*;
+
+#[cfg(feature = "rand")]
+pub mod crypto;
+
+#[cfg(feature = "rand")]
+mod random;
+#[cfg(feature = "rand")]
+pub use random::*;
+
+#[cfg(feature = "parallel")]
+mod parallel;
+#[cfg(feature = "parallel")]
+pub use parallel::*;
+
+mod roots;
+pub use roots::*;
+
+mod modular;
+pub use modular::*;
+
+mod zmodn;
+pub use zmodn::*;
@@ -0,0 +1,696 @@
+use super::*;
+
+/// Computes `a * b mod m` using a u128 intermediate.
+pub(crate) fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    ((a as u128 * b as u128) % (m as u128)) as u64
+}
+
+/// Computes `x^p mod m` for any u64 modulus.
+pub(crate) fn pow_mod(x: u64, mut p: u64, m: u64) -> u64 {
+    let mut res = 1 % m;
+    let mut x = x % m;
+    while p > 0 {
+        if p & 1 == 1 {
+            res = mul_mod(res, x, m);
+        }
+        x = mul_mod(x, x, m);
+        p >>= 1;
+    }
+    res
+}
+
+/// Computes the inverse of `a` modulo `m`, if `a` and `m` are coprime.
+pub(crate) fn inv_mod(a: u64, m: u64) -> Option<u64> {
+    let m = m as i128;
+    let (mut old_r, mut r) = ((a as i128) % m, m);
+    let (mut old_s, mut s) = (1_i128, 0_i128);
+    while r != 0 {
+        let q = old_r / r;
+        let t = old_r - q * r;
+        old_r = r;
+        r = t;
+        let t = old_s - q * s;
+        old_s = s;
+        s = t;
+    }
+    if old_r == 1 {
+        Some(old_s.rem_euclid(m) as u64)
+    } else if m == 1 {
+        Some(0)
+    } else {
+        None
+    }
+}
+
+/// Combines `x ≡ a1 (mod m1)` and `x ≡ a2 (mod m2)` for coprime `m1` and `m2`, returning the
+/// solution modulo `m1 * m2`, which must fit in a u64.
+pub(crate) fn crt(a1: u64, m1: u64, a2: u64, m2: u64) -> u64 {
+    let m1_inv = inv_mod(m1 % m2, m2).expect("crt called with non-coprime moduli");
+    let (a1m, a2m) = (a1 % m2, a2 % m2);
+    let diff = if a2m >= a1m { a2m - a1m } else { m2 - (a1m - a2m) };
+    let t = mul_mod(diff, m1_inv, m2);
+    a1 % m1 + m1 * t
+}
+
+/// Jacobi symbol of `a` over the odd number `n`, by quadratic reciprocity.
+pub(crate) fn jacobi(a: u64, n: u64) -> i64 {
+    debug_assert!(n & 1 == 1);
+    let (mut a, mut n) = (a % n, n);
+    let mut res = 1;
+    while a != 0 {
+        while a & 1 == 0 {
+            a >>= 1;
+            if n & 7 == 3 || n & 7 == 5 {
+                res = -res;
+            }
+        }
+        std::mem::swap(&mut a, &mut n);
+        if a & 3 == 3 && n & 3 == 3 {
+            res = -res;
+        }
+        a %= n;
+    }
+    if n == 1 {
+        res
+    } else {
+        0
+    }
+}
+
+/// Legendre symbol of `a` over the odd prime `p`, using Euler's criterion.
+fn legendre(a: u64, p: u64) -> i64 {
+    let a = a % p;
+    if a == 0 {
+        0
+    } else if pow_mod(a, (p - 1) / 2, p) == 1 {
+        1
+    } else {
+        -1
+    }
+}
+
+/// Finds a square root of `a` modulo the prime `p` using the Tonelli-Shanks algorithm, or
+/// `None` if `a` is not a square.
+pub(crate) fn sqrt_mod_prime(a: u64, p: u64) -> Option<u64> {
+    let a = a % p;
+    if p == 2 || a == 0 {
+        return Some(a);
+    }
+    if legendre(a, p) != 1 {
+        return None;
+    }
+    // p - 1 = q * 2^s with q odd
+    let s = (p - 1).trailing_zeros();
+    let q = (p - 1) >> s;
+    let z = (2..p).find(|&z| legendre(z, p) == -1).unwrap();
+    let mut m = s;
+    let mut c = pow_mod(z, q, p);
+    let mut t = pow_mod(a, q, p);
+    let mut r = pow_mod(a, q.div_ceil(2), p);
+    while t != 1 {
+        // the least i with t^(2^i) = 1
+        let mut i = 0;
+        let mut t2 = t;
+        while t2 != 1 {
+            t2 = mul_mod(t2, t2, p);
+            i += 1;
+        }
+        let b = pow_mod(c, 1 << (m - i - 1), p);
+        m = i;
+        c = mul_mod(b, b, p);
+        t = mul_mod(t, c, p);
+        r = mul_mod(r, b, p);
+    }
+    Some(r)
+}
+
+/// Determines whether `a` is a square modulo the prime power `p^e`.
+fn is_qr_prime_power(a: u64, p: u64, e: u64) -> bool {
+    let mut a = a;
+    let mut e = e;
+    let mut v = 0;
+    while e > 0 && a.is_multiple_of(p) {
+        a /= p;
+        e -= 1;
+        v += 1;
+    }
+    if e == 0 {
+        // a was divisible by p^e, so it is congruent to 0 = 0^2.
+        return true;
+    }
+    if v % 2 == 1 {
+        return false;
+    }
+    if p == 2 {
+        match e {
+            1 => true,
+            2 => a % 4 == 1,
+            _ => a % 8 == 1,
+        }
+    } else {
+        legendre(a, p) == 1
+    }
+}
+
+/// Determines whether `a` is a quadratic residue modulo `n`.
+///
+/// `a` is considered a quadratic residue if there is some `x` with `x^2 ≡ a (mod n)`.  Values
+/// sharing factors with `n` (including 0) are included; test `gcd(a, n) == 1` separately if
+/// only units are of interest.
+///
+/// Rather than searching for a root, this factors `n` and applies the criteria for each prime
+/// power dividing it.
+///
+/// # Panics
+///
+/// Panics if `n` is zero.
+pub fn is_quadratic_residue(a: u64, n: u64) -> bool {
+    assert!(n > 0, "is_quadratic_residue called with modulus 0");
+    factor(n).iter().all(|(p, e)| {
+        let pe = p.get().pow(e as u32);
+        is_qr_prime_power(a % pe, p.get(), e)
+    })
+}
+
+/// Calculates the multiplicative order of `a` modulo `n`.
+///
+/// This is the smallest `k > 0` with `a^k ≡ 1 (mod n)`.  It is found by factoring λ(n), which
+/// the order must divide, and stripping prime factors from it for as long as the power remains
+/// 1.
+///
+/// Returns `None` if `a` and `n` are not coprime, since then no such `k` exists.
+///
+/// # Panics
+///
+/// Panics if `n` is zero.
+pub fn multiplicative_order(a: u64, n: u64) -> Option<u64> {
+    use num::Integer;
+    assert!(n > 0, "multiplicative_order called with modulus 0");
+    if a.gcd(&n) != 1 {
+        return None;
+    }
+    if n == 1 {
+        return Some(1);
+    }
+    let lambda = carmichael_lambda(n);
+    let mut order = lambda;
+    for (q, _) in factor(lambda).iter() {
+        let q = q.get();
+        while order.is_multiple_of(q) && pow_mod(a, order / q, n) == 1 {
+            order /= q;
+        }
+    }
+    Some(order)
+}
+
+/// Baby-step giant-step search for `x < order` with `g^x ≡ h (mod n)`.
+fn bsgs(g: u64, h: u64, order: u64, n: u64) -> Option<u64> {
+    use std::collections::HashMap;
+    // ceil(sqrt(order))
+    let m = isqrt(order - 1) + 1;
+    let mut baby = HashMap::new();
+    let mut x = 1 % n;
+    for j in 0..m {
+        baby.entry(x).or_insert(j);
+        x = mul_mod(x, g, n);
+    }
+    let giant = inv_mod(pow_mod(g, m, n), n)?;
+    let mut gamma = h % n;
+    for i in 0..m {
+        if let Some(j) = baby.get(&gamma) {
+            return Some(i * m + j);
+        }
+        gamma = mul_mod(gamma, giant, n);
+    }
+    None
+}
+
+/// Pohlig-Hellman reduction for a base that is a unit modulo `n`.
+fn discrete_log_unit(g: u64, h: u64, n: u64) -> Option<(u64, u64)> {
+    let order = multiplicative_order(g, n)?;
+    let g_inv = inv_mod(g, n)?;
+    let mut x = 0;
+    let mut modulus = 1;
+    for (q, e) in factor(order).iter() {
+        let q = q.get();
+        let qe = q.pow(e as u32);
+        // Project into the subgroup of order q^e and solve one base-q digit at a time.
+        let gi = pow_mod(g, order / qe, n);
+        let gi_inv = pow_mod(g_inv, order / qe, n);
+        let hi = pow_mod(h, order / qe, n);
+        let gamma = pow_mod(gi, qe / q, n);
+        let mut xi = 0;
+        let mut qk = 1;
+        for _ in 0..e {
+            let t = mul_mod(pow_mod(gi_inv, xi, n), hi, n);
+            let hk = pow_mod(t, qe / qk / q, n);
+            let d = bsgs(gamma, hk, q, n)?;
+            xi += d * qk;
+            qk *= q;
+        }
+        x = crt(x, modulus, xi, qe);
+        modulus *= qe;
+    }
+    if pow_mod(g, x, n) == h % n {
+        Some((x, order))
+    } else {
+        None
+    }
+}
+
+/// Computes the discrete logarithm of `target` to the given `base` modulo `modulus`.
+///
+/// Returns the smallest `x` with `base^x ≡ target (mod modulus)`, or `None` if there is no
+/// such `x`.
+///
+/// The order of `base` is factored with `factor()`, the problem is solved in each prime-power
+/// subgroup with baby-step giant-step, and the partial results are combined by the Chinese
+/// remainder theorem.  The cost is dominated by the square root of the largest prime factor of
+/// the order, so this is practical whenever that factor is below roughly 2^40.  Bases that share
+/// factors with `modulus` are also handled.
+///
+/// # Panics
+///
+/// Panics if `modulus` is zero.
+pub fn discrete_log(base: u64, target: u64, modulus: u64) -> Option<u64> {
+    use num::Integer;
+    assert!(modulus > 0, "discrete_log called with modulus 0");
+    let n = modulus;
+    let base = base % n;
+    let target = target % n;
+    if base.gcd(&n) == 1 {
+        return discrete_log_unit(base, target, n).map(|(x, _)| x);
+    }
+
+    // No prime divides n more than 63 times, so past this point base^x is 0 modulo the part of
+    // n sharing primes with base.  Check the short preperiod directly.
+    const PREPERIOD: u64 = 64;
+    let mut x = 1 % n;
+    for k in 0..PREPERIOD {
+        if x == target {
+            return Some(k);
+        }
+        x = mul_mod(x, base, n);
+    }
+    let mut n2 = n;
+    let mut g = n2.gcd(&base);
+    while g > 1 {
+        n2 /= g;
+        g = n2.gcd(&g);
+    }
+    let n1 = n / n2;
+    if !target.is_multiple_of(n1) {
+        return None;
+    }
+    let (y, order) = discrete_log_unit(base % n2, target % n2, n2)?;
+    let k = if y >= PREPERIOD {
+        y
+    } else {
+        y + (PREPERIOD - y).div_ceil(order) * order
+    };
+    Some(k)
+}
+
+/// Describes the structure of the multiplicative group of units modulo `n`.
+///
+/// Returns the invariant factors `[d1, d2, ..., dk]` of (Z/nZ)*, so that the group is
+/// isomorphic to the product of cyclic groups of orders `d1`, `d2`, ..., `dk`, and each `di`
+/// divides the next.  The product of the factors is φ(n), and the last one is λ(n).  The group
+/// is cyclic exactly when there is at most one factor; for `n` of 1 or 2 the group is trivial
+/// and the result is empty.
+///
+/// # Panics
+///
+/// Panics if `n` is zero.
+pub fn unit_group_structure(n: u64) -> Vec<u64> {
+    use std::collections::BTreeMap;
+    assert!(n > 0, "unit_group_structure called with modulus 0");
+    // Cyclic factors of prime-power order, grouped by prime.
+    let mut primary: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+    let mut add = |q: u64, qf: u64| primary.entry(q).or_default().push(qf);
+    for (p, e) in factor(n).iter() {
+        let p = p.get();
+        if p == 2 {
+            if e >= 2 {
+                add(2, 2);
+            }
+            if e >= 3 {
+                add(2, 1 << (e - 2));
+            }
+        } else {
+            for (q, f) in factor(p - 1).iter() {
+                add(q.get(), q.get().pow(f as u32));
+            }
+            if e > 1 {
+                add(p, p.pow(e as u32 - 1));
+            }
+        }
+    }
+    let len = primary.values().map(|v| v.len()).max().unwrap_or(0);
+    let mut res = vec![1; len];
+    for mut powers in primary.into_values() {
+        powers.sort_unstable_by(|a, b| b.cmp(a));
+        for (i, qf) in powers.into_iter().enumerate() {
+            res[i] *= qf;
+        }
+    }
+    res.reverse();
+    res
+}
+
+/// The square roots of 1 modulo the prime power `q = p^e`.
+fn prime_power_sqrts_of_one(p: u64, e: u64, q: u64) -> Vec<u64> {
+    if p != 2 {
+        vec![1, q - 1]
+    } else if e == 1 {
+        vec![1]
+    } else if e == 2 {
+        vec![1, 3]
+    } else {
+        vec![1, q / 2 - 1, q / 2 + 1, q - 1]
+    }
+}
+
+/// Counts the solutions of `x^2 ≡ 1 (mod n)` with `0 <= x < n`.
+///
+/// Calculated directly from the factorization of `n`, without enumerating anything.
+///
+/// # Panics
+///
+/// Panics if `n` is zero.
+pub fn sqrt_of_one_count(n: u64) -> u64 {
+    assert!(n > 0, "sqrt_of_one_count called with modulus 0");
+    factor(n).iter().map(|(p, e)| {
+        match (p.get(), e) {
+            (2, 1) => 1,
+            (2, 2) => 2,
+            (2, _) => 4,
+            _ => 2,
+        }
+    }).product()
+}
+
+/// Returns an iterator over all `x` with `0 <= x < n` and `x^2 ≡ 1 (mod n)`.
+///
+/// The roots for each prime power dividing `n` are combined with the Chinese remainder theorem.
+/// No particular order of the roots is guaranteed.
+///
+/// # Panics
+///
+/// Panics if `n` is zero.
+pub fn sqrts_of_one(n: u64) -> SqrtsOfOne {
+    assert!(n > 0, "sqrts_of_one called with modulus 0");
+    let parts = factor(n).iter().map(|(p, e)| {
+        let p = p.get();
+        let q = p.pow(e as u32);
+        let cofactor = n / q;
+        let coef = mul_mod(cofactor, inv_mod(cofactor % q, q).unwrap(), n);
+        (prime_power_sqrts_of_one(p, e, q), coef)
+    }).collect::<Vec<_>>();
+    let counter = Some(vec![0; parts.len()]);
+    SqrtsOfOne { n, parts, counter }
+}
+
+/// Iterator over the square roots of 1 modulo some `n`, created by `sqrts_of_one()`.
+#[derive(Clone, Debug)]
+pub struct SqrtsOfOne {
+    n: u64,
+    /// The roots modulo each prime power dividing n, with that prime power's CRT coefficient.
+    parts: Vec<(Vec<u64>, u64)>,
+    /// Mixed-radix counter choosing one root per prime power, or None when exhausted.
+    counter: Option<Vec<usize>>,
+}
+
+impl Iterator for SqrtsOfOne {
+    type Item = u64;
+    fn next(&mut self) -> Option<Self::Item> {
+        let counter = self.counter.as_mut()?;
+        let n = self.n;
+        let mut x = 0;
+        for ((roots, coef), &i) in self.parts.iter().zip(counter.iter()) {
+            x = ((x as u128 + mul_mod(roots[i], *coef, n) as u128) % n as u128) as u64;
+        }
+        let mut pos = 0;
+        loop {
+            if pos == counter.len() {
+                self.counter = None;
+                break;
+            }
+            counter[pos] += 1;
+            if counter[pos] < self.parts[pos].0.len() {
+                break;
+            }
+            counter[pos] = 0;
+            pos += 1;
+        }
+        Some(x)
+    }
+}
+
+/// Calculates the length of the repeating part of the decimal expansion of `1/n`.
+///
+/// Factors of 2 and 5 only delay the start of the repetition, so this is the multiplicative
+/// order of 10 modulo what is left of `n` once they are removed.  Returns 0 when the expansion
+/// terminates.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::decimal_period;
+///
+/// fn main() {
+///     assert_eq!(decimal_period(7), 6);  // 1/7 = 0.(142857)
+///     assert_eq!(decimal_period(12), 1);  // 1/12 = 0.08(3)
+///     assert_eq!(decimal_period(40), 0);  // 1/40 = 0.025
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `n` is zero.
+pub fn decimal_period(n: u64) -> u64 {
+    assert!(n > 0, "decimal_period called with n = 0");
+    let mut m = n;
+    while m & 1 == 0 {
+        m >>= 1;
+    }
+    while m.is_multiple_of(5) {
+        m /= 5;
+    }
+    if m == 1 {
+        0
+    } else {
+        multiplicative_order(10, m).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn brute_force_squares(n: u64) -> BTreeSet<u64> {
+        (0..n).map(|x| mul_mod(x, x, n)).collect()
+    }
+
+    #[test]
+    fn small_quadratic_residues() {
+        for n in 1..300 {
+            let squares = brute_force_squares(n);
+            for a in 0..2*n {
+                assert_eq!(is_quadratic_residue(a, n), squares.contains(&(a % n)),
+                           "is_quadratic_residue({}, {})", a, n);
+            }
+        }
+    }
+
+    #[test]
+    fn big_quadratic_residues() {
+        let p = MAX_U64_PRIME;
+        for x in 1..100 {
+            assert!(is_quadratic_residue(mul_mod(x, x, p), p));
+        }
+        // p = 2^64 - 59 is 1 mod 4, so -1 is a square.
+        assert!(is_quadratic_residue(p - 1, p));
+        // 2^61 - 1 is 3 mod 4, so -1 is not a square.
+        let m61 = (1 << 61) - 1;
+        assert!(!is_quadratic_residue(m61 - 1, m61));
+        assert!(!is_quadratic_residue(m61 - 1, m61 * 5));
+    }
+
+    fn brute_force_order(a: u64, n: u64) -> Option<u64> {
+        let mut x = a % n;
+        for k in 1..=n {
+            if x == 1 % n {
+                return Some(k);
+            }
+            x = mul_mod(x, a, n);
+        }
+        None
+    }
+
+    #[test]
+    fn small_orders() {
+        for n in 1..200 {
+            for a in 0..n {
+                assert_eq!(multiplicative_order(a, n), brute_force_order(a, n),
+                           "multiplicative_order({}, {})", a, n);
+            }
+        }
+    }
+
+    #[test]
+    fn decimal_periods() {
+        for n in 1..2000_u64 {
+            // long division, until a remainder repeats
+            let mut seen = std::collections::HashMap::new();
+            let mut r = 1 % n;
+            let mut i = 0;
+            while r != 0 && !seen.contains_key(&r) {
+                seen.insert(r, i);
+                r = r * 10 % n;
+                i += 1;
+            }
+            let expected = if r == 0 { 0 } else { i - seen[&r] };
+            assert_eq!(decimal_period(n), expected, "decimal_period({})", n);
+        }
+        // 1/(10^k - 1) repeats with period k
+        assert_eq!(decimal_period(9_999_999_999_999_999_999), 19);
+        assert!((MAX_U64_PRIME - 1).is_multiple_of(decimal_period(MAX_U64_PRIME)));
+    }
+
+    #[test]
+    fn jacobi_symbols() {
+        for n in (1..500).step_by(2) {
+            let pf = factor(n);
+            for a in 0..500 {
+                let expected: i64 = pf.iter().map(|(p, e)| {
+                    if p.get() == 1 { 1 } else { legendre(a, p.get()).pow(e as u32) }
+                }).product();
+                assert_eq!(jacobi(a, n), expected, "jacobi({}, {})", a, n);
+            }
+        }
+        assert_eq!(jacobi(u64::MAX - 1, MAX_U64_PRIME), legendre(u64::MAX - 1, MAX_U64_PRIME));
+    }
+
+    #[test]
+    fn prime_sqrts() {
+        for p in [2_u64, 3, 5, 7, 13, 17, 41, 97, 257, 65537, 1_000_000_007, MAX_U64_PRIME].iter() {
+            let p = *p;
+            for a in (0..200).chain(p.saturating_sub(200)..p) {
+                match sqrt_mod_prime(a, p) {
+                    Some(r) => assert_eq!(mul_mod(r, r, p), a % p, "sqrt({}) mod {}", a, p),
+                    None => assert!(p > 2 && legendre(a, p) == -1),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn big_orders() {
+        // 2 generates a subgroup of order 61 modulo the Mersenne prime 2^61 - 1.
+        assert_eq!(multiplicative_order(2, (1 << 61) - 1), Some(61));
+        let p = MAX_U64_PRIME;
+        let k = multiplicative_order(3, p).unwrap();
+        assert_eq!(pow_mod(3, k, p), 1);
+        assert!((p - 1).is_multiple_of(k));
+    }
+
+    fn brute_force_log(base: u64, target: u64, n: u64) -> Option<u64> {
+        let mut x = 1 % n;
+        for k in 0..n+70 {
+            if x == target % n {
+                return Some(k);
+            }
+            x = mul_mod(x, base, n);
+        }
+        None
+    }
+
+    #[test]
+    fn small_discrete_logs() {
+        for n in 1..80 {
+            for base in 0..n {
+                for target in 0..n {
+                    assert_eq!(discrete_log(base, target, n), brute_force_log(base, target, n),
+                               "discrete_log({}, {}, {})", base, target, n);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn big_discrete_logs() {
+        // 2^61 - 2 is very smooth, so logs modulo 2^61 - 1 are cheap.
+        let p = (1 << 61) - 1;
+        let order = multiplicative_order(3, p).unwrap();
+        for &x in [0, 1, 12345, 1 << 40, p - 3].iter() {
+            let target = pow_mod(3, x, p);
+            assert_eq!(discrete_log(3, target, p), Some(x % order));
+        }
+        // Beyond the preperiod, powers of 2 mod 2^62 * 3 are 0 mod 2^62 and cycle mod 3.
+        let n = (1 << 62) * 3;
+        assert_eq!(discrete_log(2, pow_mod(2, 101, n), n), Some(63));
+        assert_eq!(discrete_log(2, 5, n), None);
+    }
+
+    #[test]
+    fn small_unit_groups() {
+        use num::Integer;
+        for n in 1..300 {
+            let ds = unit_group_structure(n);
+            assert_eq!(ds.iter().product::<u64>(), euler_totient(n), "unit_group_structure({})", n);
+            for w in ds.windows(2) {
+                assert!(w[1].is_multiple_of(w[0]), "unit_group_structure({}) = {:?}", n, ds);
+            }
+            let lambda = carmichael_lambda(n);
+            assert_eq!(ds.last().copied().unwrap_or(1), lambda);
+            // The number of solutions of x^k = 1 pins down the group up to isomorphism.
+            factor(lambda).for_all_divisors(|k| {
+                let count = (0..n).filter(|x| x.gcd(&n) == 1 && pow_mod(*x, k, n) == 1 % n).count();
+                let expected: u64 = ds.iter().map(|d| d.gcd(&k)).product();
+                assert_eq!(count as u64, expected, "unit_group_structure({}), k={}", n, k);
+            });
+        }
+        assert_eq!(unit_group_structure(1), vec![]);
+        assert_eq!(unit_group_structure(8), vec![2, 2]);
+        assert_eq!(unit_group_structure(15), vec![2, 4]);
+    }
+
+    #[test]
+    fn small_sqrts_of_one() {
+        for n in 1..2000 {
+            let expected: BTreeSet<u64> = (0..n).filter(|x| mul_mod(*x, *x, n) == 1 % n).collect();
+            let found: Vec<u64> = sqrts_of_one(n).collect();
+            assert_eq!(found.len() as u64, sqrt_of_one_count(n), "sqrt_of_one_count({})", n);
+            assert_eq!(found.into_iter().collect::<BTreeSet<u64>>(), expected, "sqrts_of_one({})", n);
+            assert_eq!(sqrt_of_one_count(n), expected.len() as u64);
+        }
+    }
+
+    #[test]
+    fn big_sqrts_of_one() {
+        let n = u64::MAX;  // 3 * 5 * 17 * 257 * 641 * 65537 * 6700417
+        assert_eq!(sqrt_of_one_count(n), 128);
+        for x in sqrts_of_one(n) {
+            assert_eq!(mul_mod(x, x, n), 1);
+        }
+    }
+
+    #[test]
+    fn inverses() {
+        for m in 1..200 {
+            for a in 0..m {
+                let expected = (0..m).find(|x| mul_mod(a, *x, m) == 1 % m);
+                assert_eq!(inv_mod(a, m), expected, "inv_mod({}, {})", a, m);
+            }
+        }
+    }
+
+    #[test]
+    fn pow_mod_edges() {
+        assert_eq!(pow_mod(5, 0, 1), 0);
+        assert_eq!(pow_mod(0, 0, 7), 1);
+        assert_eq!(pow_mod(u64::MAX, 2, MAX_U64_PRIME), 58 * 58);
+    }
+}
@@ -0,0 +1,140 @@
+use super::*;
+
+use num::One;
+use std::ops::Mul;
+
+/// Evaluates a multiplicative function at `n`.
+///
+/// Factors `n` and multiplies together `f(p, k)` for every prime power `p^k` exactly dividing
+/// `n`.  `f` only needs to describe the function on prime powers; the value at 1 is `T::one()`.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::eval_multiplicative;
+///
+/// fn main() {
+///     // the number of divisors
+///     assert_eq!(eval_multiplicative(360, |_, k| k + 1), 24);
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `n` is 0.
+pub fn eval_multiplicative<T, F>(n: u64, f: F) -> T
+    where T: One + Mul<Output = T>, F: FnMut(Prime, u64) -> T
+{
+    factor(n).eval_multiplicative(f)
+}
+
+impl PrimeFactorization {
+    /// Evaluates a multiplicative function given by its values `f(p, k)` on prime powers `p^k`.
+    pub fn eval_multiplicative<T, F>(&self, mut f: F) -> T
+        where T: One + Mul<Output = T>, F: FnMut(Prime, u64) -> T
+    {
+        self.iter().fold(T::one(), |acc, (p, k)| acc * f(p, k))
+    }
+}
+
+/// A multiplicative arithmetic function, described by its values on prime powers.
+///
+/// Closures taking `(Prime, u64)` implement this trait, so they can be evaluated and combined
+/// directly.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::{ MultiplicativeFn, Prime };
+///
+/// fn main() {
+///     let totient = |p: Prime, k: u64| (p.get() - 1) * p.get().pow(k as u32 - 1);
+///     let divisor_count = |_: Prime, k: u64| k + 1;
+///     assert_eq!(totient.eval(12), 4);
+///     assert_eq!(totient.pointwise_mul(divisor_count).eval(12), 24);
+/// }
+/// ```
+pub trait MultiplicativeFn {
+    /// The type of the function's values.
+    type Output: One + Mul<Output = Self::Output>;
+
+    /// The value of the function at `p^k`, where `k >= 1`.
+    fn at_prime_power(&self, p: Prime, k: u64) -> Self::Output;
+
+    /// The value of the function at the number represented by `pf`.
+    fn eval_factored(&self, pf: &PrimeFactorization) -> Self::Output {
+        pf.eval_multiplicative(|p, k| self.at_prime_power(p, k))
+    }
+
+    /// The value of the function at `n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0.
+    fn eval(&self, n: u64) -> Self::Output {
+        self.eval_factored(&factor(n))
+    }
+
+    /// The pointwise product `n -> f(n) g(n)`, which is also multiplicative.
+    fn pointwise_mul<G>(self, other: G) -> PointwiseProduct<Self, G>
+        where Self: Sized, G: MultiplicativeFn<Output = Self::Output>
+    {
+        PointwiseProduct { f: self, g: other }
+    }
+}
+
+impl<T, F> MultiplicativeFn for F
+    where T: One + Mul<Output = T>, F: Fn(Prime, u64) -> T
+{
+    type Output = T;
+    fn at_prime_power(&self, p: Prime, k: u64) -> T {
+        self(p, k)
+    }
+}
+
+/// The pointwise product of two multiplicative functions.
+///
+/// Created by `MultiplicativeFn::pointwise_mul()`.
+#[derive(Clone, Copy, Debug)]
+pub struct PointwiseProduct<F, G> {
+    f: F,
+    g: G,
+}
+
+impl<F, G> MultiplicativeFn for PointwiseProduct<F, G>
+    where F: MultiplicativeFn, G: MultiplicativeFn<Output = F::Output>
+{
+    type Output = F::Output;
+    fn at_prime_power(&self, p: Prime, k: u64) -> Self::Output {
+        self.f.at_prime_power(p, k) * self.g.at_prime_power(p, k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_known_functions() {
+        for n in 1..5000 {
+            assert_eq!(eval_multiplicative(n, |_, k| k + 1), divisor_count(n));
+            assert_eq!(eval_multiplicative(n, |p, k| (p.get() - 1) * p.get().pow(k as u32 - 1)),
+                       euler_totient(n));
+            assert_eq!(eval_multiplicative(n, |_, k| if k == 1 { -1 } else { 0 }), mobius(n, 1));
+            assert_eq!(eval_multiplicative(n, |p, k| p.get().pow(k as u32)), n);
+        }
+    }
+
+    #[test]
+    fn pointwise_products() {
+        let id = |p: Prime, k: u64| p.get().pow(k as u32);
+        let d = |_: Prime, k: u64| k + 1;
+        let mu = |_: Prime, k: u64| if k == 1 { -1_i64 } else { 0 };
+        let mu2 = mu.pointwise_mul(mu);
+        for n in 1..5000 {
+            assert_eq!(id.pointwise_mul(d).eval(n), n * divisor_count(n));
+            assert_eq!(mu2.eval(n), mobius(n, 1).abs());
+            assert_eq!(d.eval_factored(&factor(n)), divisor_count(n));
+        }
+    }
+}
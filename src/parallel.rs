@@ -0,0 +1,61 @@
+use super::sieve::{ inclusive_bounds, SegmentSieve, SEGMENT_LEN };
+
+use rayon::prelude::*;
+use std::ops::RangeBounds;
+use std::sync::Arc;
+
+/// Produces the primes in a range as a rayon parallel iterator.
+///
+/// The range is split into sieve segments, which rayon hands out to its worker threads, and each
+/// segment is sieved independently.  The table of sieving primes is built once and shared.  The
+/// iterator is indexed by segment, so order-preserving operations like `collect()` still give
+/// the primes in ascending order.
+///
+/// Requires the `parallel` feature.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::par_primes_in;
+/// use rayon::prelude::*;
+///
+/// fn main() {
+///     let sum: u64 = par_primes_in(..2_000_000).sum();
+///     assert_eq!(sum, 142_913_828_922);
+///     let ps: Vec<u64> = par_primes_in(100..=130).collect();
+///     assert_eq!(ps, vec![101, 103, 107, 109, 113, 127]);
+/// }
+/// ```
+pub fn par_primes_in<R: RangeBounds<u64>>(range: R) -> impl ParallelIterator<Item = u64> {
+    let (lo, hi, segments) = match inclusive_bounds(&range) {
+        Some((lo, hi)) => (lo, hi, (hi - lo) / SEGMENT_LEN + 1),
+        None => (0, 0, 0),
+    };
+    let sieve = Arc::new(SegmentSieve::new(hi));
+    (0..segments).into_par_iter().flat_map_iter(move |i| {
+        let seg_lo = lo + i * SEGMENT_LEN;
+        let seg_hi = seg_lo.saturating_add(SEGMENT_LEN - 1).min(hi);
+        let mut primes = Vec::new();
+        sieve.primes_in(seg_lo, seg_hi, &mut primes);
+        primes
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PrimeIter;
+
+    #[test]
+    fn compare_par_primes() {
+        let expected: Vec<u64> = PrimeIter::range(0, 3_000_000).collect();
+        assert_eq!(par_primes_in(0..3_000_000).collect::<Vec<u64>>(), expected);
+        for &(a, b) in [(0, 0), (10, 10), (0, 2), (0, 3), (1000, 1000 + 3 * SEGMENT_LEN),
+                        (1 << 40, (1 << 40) + 100_000), (u64::MAX - 2000, u64::MAX)].iter() {
+            let expected: Vec<u64> = PrimeIter::range(a, b).collect();
+            assert_eq!(par_primes_in(a..b).collect::<Vec<u64>>(), expected, "{}..{}", a, b);
+        }
+        assert_eq!(par_primes_in(u64::MAX - 1000..).count(), 21);
+        assert_eq!(par_primes_in(..).take_any(5).count(), 5);
+    }
+}
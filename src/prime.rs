@@ -1,5 +1,11 @@
 
 
+use super::modular::{ jacobi, mul_mod };
+use super::roots::isqrt;
+use super::sieve::inclusive_bounds;
+
+use std::ops::{ Bound, RangeBounds };
+
 /// Wrapper type certifying that a u64 is prime.
 #[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
 pub struct Prime {
@@ -27,6 +33,10 @@ impl Prime {
         }
     }
     /// Forges a primality certificate.  Use with caution.
+    ///
+    /// # Safety
+    ///
+    /// `n` must actually be prime.  Code receiving a `Prime` is allowed to rely on it.
     pub unsafe fn new_unsafe(n: u64) -> Prime {
         Prime { n }
     }
@@ -76,7 +86,7 @@ pub fn is_u64_prime(n: u64) -> bool
         sprp_u64(n, 2) && sprp_u64(n, 3)
     } else if n < 4_759_123_141 {
         // if n < 4,759,123,141, it is enough to test a = 2, 7, and 61;
-        if n <= std::u32::MAX as u64 {
+        if n <= u32::MAX as u64 {
             sprp_u64(n, 2) && sprp_u64(n, 7) && sprp_u64(n, 61)
         } else {
             let n = n as u128;
@@ -121,7 +131,7 @@ fn sprp_u64(n: u64, a: u8) -> bool {
     false
 }
 
-// assumes both x*x and m*m < std::u64::MAX
+// assumes both x*x and m*m < u64::MAX
 fn pow_mod_u64(mut x: u64, mut p: u64, m: u64) -> u64 {
     let mut res = 1;
     loop {
@@ -139,7 +149,7 @@ fn pow_mod_u64(mut x: u64, mut p: u64, m: u64) -> u64 {
     }
     res
 }
-// assumes both x*x and m*m < std::u128::MAX
+// assumes both x*x and m*m < u128::MAX
 fn pow_mod_u128(mut x: u128, mut p: u128, m: u128) -> u128 {
     let mut res = 1;
     loop {
@@ -177,11 +187,163 @@ fn sprp_u128(n: u128, a: u8) -> bool {
     false
 }
 
+/// Finds the smallest prime strictly greater than `n`.
+///
+/// Returns `None` when `n` is at least `MAX_U64_PRIME`, since there is no larger u64 prime.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::next_prime;
+///
+/// fn main() {
+///     assert_eq!(next_prime(13).map(|p| p.get()), Some(17));
+///     assert_eq!(next_prime(u64::MAX), None);
+/// }
+/// ```
+pub fn next_prime(n: u64) -> Option<Prime> {
+    if n < 2 {
+        return Prime::new(2);
+    }
+    if n >= MAX_U64_PRIME {
+        return None;
+    }
+    // the next odd number, then every other one
+    let start = (n + 1) | 1;
+    (start..=MAX_U64_PRIME).step_by(2).find_map(Prime::new)
+}
+
+/// Finds the largest prime strictly less than `n`.
+///
+/// Returns `None` when `n` is 2 or less.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::prev_prime;
+///
+/// fn main() {
+///     assert_eq!(prev_prime(13).map(|p| p.get()), Some(11));
+///     assert_eq!(prev_prime(2), None);
+/// }
+/// ```
+pub fn prev_prime(n: u64) -> Option<Prime> {
+    if n <= 2 {
+        return None;
+    }
+    if n == 3 {
+        return Prime::new(2);
+    }
+    // the previous odd number, then every other one
+    let start = if n & 1 == 0 { n - 1 } else { n - 2 };
+    (3..=start).rev().step_by(2).find_map(Prime::new)
+}
+
+/// Finds some prime in a range, or `None` if it has none.
+///
+/// The search starts at the midpoint of the range, and looks upward and then downward from there,
+/// so a wide range is settled after about one prime gap's worth of candidates.  Which prime is
+/// returned is unspecified beyond being deterministic; use `next_prime()` for the smallest.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::any_prime_in;
+///
+/// fn main() {
+///     let p = any_prime_in(1_000_000..2_000_000).unwrap().get();
+///     assert!(p >= 1_000_000 && p < 2_000_000);
+///     assert_eq!(any_prime_in(24..29), None);
+/// }
+/// ```
+pub fn any_prime_in<R: RangeBounds<u64>>(range: R) -> Option<Prime> {
+    let (lo, hi) = inclusive_bounds(&range)?;
+    let mid = lo + (hi - lo) / 2;
+    match Prime::new(mid).or_else(|| next_prime(mid)) {
+        Some(p) if p.get() <= hi => Some(p),
+        _ => prev_prime(mid).filter(|p| p.get() >= lo),
+    }
+}
+
+/// Finds some prime strictly between `a` and `b`, or `None` if there is none.
+///
+/// By Bertrand's postulate `prime_between(n, 2 * n)` always succeeds for `n >= 2`, which makes
+/// this handy for sizing hash tables.  See `any_prime_in()` for how the prime is chosen.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::prime_between;
+///
+/// fn main() {
+///     let p = prime_between(1000, 2000).unwrap().get();
+///     assert!(1000 < p && p < 2000);
+///     assert_eq!(prime_between(13, 17).map(|p| p.get()), None);
+/// }
+/// ```
+pub fn prime_between(a: u64, b: u64) -> Option<Prime> {
+    any_prime_in((Bound::Excluded(a), Bound::Excluded(b)))
+}
+
+/// Determines whether `n` passes Khashin's Frobenius probable prime test.
+///
+/// The test takes the smallest odd prime `c` with Jacobi symbol `(c/n) = -1` and checks that
+/// `(1 + √c)^n ≡ 1 - √c (mod n)`, working in `Z[√c] / nZ`.  This is what happens for every prime,
+/// where raising to the `n`-th power acts as conjugation, and no composite is known to pass.  It
+/// costs a few times as much as a single Miller-Rabin test.
+///
+/// Unlike `is_u64_prime()` this is not proven correct for all u64, so it is offered as an
+/// independent check rather than a replacement.
+pub fn is_frobenius_probable_prime(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    if n & 1 == 0 {
+        return n == 2;
+    }
+    let r = isqrt(n);
+    if r * r == n {
+        // no c has (c/n) = -1
+        return false;
+    }
+    let mut c = 3;
+    loop {
+        match jacobi(c, n) {
+            -1 => break,
+            0 => return c == n,
+            _ => c = next_prime_after(c),
+        }
+    }
+    let c = c % n;
+    // (a + b√c) * (d + e√c) mod n
+    let mul = |(a, b): (u64, u64), (d, e): (u64, u64)| {
+        let re = (mul_mod(a, d, n) as u128 + mul_mod(mul_mod(b, e, n), c, n) as u128) % n as u128;
+        let im = (mul_mod(a, e, n) as u128 + mul_mod(b, d, n) as u128) % n as u128;
+        (re as u64, im as u64)
+    };
+    let mut res = (1, 0);
+    let mut x = (1, 1);
+    let mut e = n;
+    while e > 0 {
+        if e & 1 == 1 {
+            res = mul(res, x);
+        }
+        x = mul(x, x);
+        e >>= 1;
+    }
+    res == (1, n - 1)
+}
+
+/// The smallest prime above the small odd prime `p`.
+fn next_prime_after(p: u64) -> u64 {
+    (p + 2..).step_by(2).find(|&q| is_u64_prime(q)).unwrap()
+}
+
 #[test]
 fn dump_end() {
-    for p in (std::u64::MAX - 1000)..=std::u64::MAX {
+    for p in (u64::MAX - 1000)..=u64::MAX {
         if is_u64_prime(p) {
-            println!("{} (2^64 - {}) is prime", p, std::u64::MAX - p + 1);
+            println!("{} (2^64 - {}) is prime", p, u64::MAX - p + 1);
         }
     }
     // results appear to match https://primes.utm.edu/lists/2small/0bit.html
@@ -227,14 +389,63 @@ mod tests {
             use gmp::mpz::{ Mpz, ProbabPrimeResult };
             let n_gmp = Mpz::from(n);
             let gmp_pp_res = n_gmp.probab_prime(100);
-            let gmp_pp_res: bool = match gmp_pp_res {
-                ProbabPrimeResult::NotPrime => false,
-                _ => true,
-            };
+            let gmp_pp_res = !matches!(gmp_pp_res, ProbabPrimeResult::NotPrime);
             assert_eq!(gmp_pp_res, is_prime_res, "excessive gmp test failed for n={}", n);
         }
 
     }
+    #[test]
+    fn primes_between() {
+        for a in 0..300 {
+            for b in a..300 {
+                let any = (a + 1..b).any(is_u64_prime);
+                let p = prime_between(a, b).map(|p| p.get());
+                assert_eq!(p.is_some(), any, "between {} and {}", a, b);
+                assert!(p.is_none_or(|p| a < p && p < b && is_u64_prime(p)));
+                let p = any_prime_in(a..=b).map(|p| p.get());
+                assert!(p.is_none_or(|p| a <= p && p <= b && is_u64_prime(p)));
+            }
+        }
+        for n in (2..1_000_000).step_by(997) {
+            assert!(prime_between(n, 2 * n).is_some(), "Bertrand fails for {}", n);
+        }
+        assert_eq!(any_prime_in(u64::MAX - 58..).map(|p| p.get()), Some(MAX_U64_PRIME));
+        assert_eq!(any_prime_in(MAX_U64_PRIME + 1..), None);
+        assert_eq!(any_prime_in(..), any_prime_in(..=u64::MAX));
+    }
+
+    #[test]
+    fn next_and_prev() {
+        let sieve = Sieve::new(LIMIT as usize);
+        for n in 0..LIMIT {
+            let next = (n + 1..).find(|&m| sieve.is_prime(m as usize));
+            assert_eq!(next_prime(n).map(|p| p.get()), next, "next_prime({})", n);
+            let prev = (0..n).rev().find(|&m| sieve.is_prime(m as usize));
+            assert_eq!(prev_prime(n).map(|p| p.get()), prev, "prev_prime({})", n);
+        }
+        assert_eq!(next_prime(MAX_U64_PRIME - 1).map(|p| p.get()), Some(MAX_U64_PRIME));
+        assert_eq!(next_prime(MAX_U64_PRIME), None);
+        assert_eq!(prev_prime(u64::MAX).map(|p| p.get()), Some(MAX_U64_PRIME));
+        assert_eq!(prev_prime(MAX_U64_PRIME).map(|p| p.get()), Some(18_446_744_073_709_551_533));
+    }
+
+    #[test]
+    fn frobenius() {
+        let sieve = Sieve::new(LIMIT as usize);
+        for n in 0..LIMIT {
+            assert_eq!(is_frobenius_probable_prime(n), sieve.is_prime(n as usize), "n={}", n);
+        }
+        // strong pseudoprimes to several bases, Carmichael numbers, and the largest primes
+        let composites = [3_215_031_751, 3_825_123_056_546_413_051, 561, 41_041, 825_265, 321_197_185, 5_394_826_801, 232_250_619_601,
+                          9_746_347_772_161, u64::MAX, 4_294_967_291 * 4_294_967_279];
+        for &n in composites.iter() {
+            assert!(!is_frobenius_probable_prime(n), "n={}", n);
+        }
+        for n in u64::MAX - 1000..=u64::MAX {
+            assert_eq!(is_frobenius_probable_prime(n), is_u64_prime(n), "n={}", n);
+        }
+    }
+
     #[test]
     fn big_numbers() {
         use std::num::Wrapping;
@@ -258,7 +469,7 @@ mod tests {
                 test_prime_excessive(n);
             }
         }
-        for n in (std::u64::MAX - radius)..=std::u64::MAX {
+        for n in (u64::MAX - radius)..=u64::MAX {
             test_prime_excessive(n);
         }
     }
@@ -0,0 +1,126 @@
+use super::sieve::{ inclusive_bounds, SegmentSieve };
+
+use std::fmt;
+use std::ops::RangeBounds;
+
+/// The primes in a fixed range, stored as a bitset for fast membership tests.
+///
+/// Building the set sieves the whole range once, like `SievedPrimeIter`, and stores one bit per
+/// number, so a range of width `w` takes `w / 8` bytes.  After that `contains()` is a single bit
+/// lookup, which beats repeated `is_u64_prime()` calls when many numbers in a dense window need
+/// testing.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::PrimeSet;
+///
+/// fn main() {
+///     let set = PrimeSet::new(1_000_000..1_001_000);
+///     assert!(set.contains(1_000_003));
+///     assert!(!set.contains(1_000_005));
+///     assert!(!set.contains(7));  // outside the range
+///     assert_eq!(set.len(), 75);
+///     assert_eq!(set.iter().next(), Some(1_000_003));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct PrimeSet {
+    /// Inclusive bounds of the range, or None if it is empty.
+    bounds: Option<(u64, u64)>,
+    /// Bit `i` is set when `lo + i` is prime.
+    bits: Vec<u64>,
+    len: usize,
+}
+
+impl PrimeSet {
+    /// Sieves the range and records its primes.
+    pub fn new<R: RangeBounds<u64>>(range: R) -> Self {
+        let bounds = inclusive_bounds(&range);
+        let mut bits = Vec::new();
+        let mut len = 0;
+        if let Some((lo, hi)) = bounds {
+            bits = vec![0; ((hi - lo) / 64 + 1) as usize];
+            SegmentSieve::new(hi).for_segments(lo, hi, |primes| {
+                for &p in primes {
+                    let i = p - lo;
+                    bits[(i / 64) as usize] |= 1 << (i % 64);
+                }
+                len += primes.len();
+                true
+            });
+        }
+        PrimeSet { bounds, bits, len }
+    }
+
+    /// Decides whether `n` is a prime in the set's range.
+    pub fn contains(&self, n: u64) -> bool {
+        match self.bounds {
+            Some((lo, hi)) if lo <= n && n <= hi => {
+                let i = n - lo;
+                self.bits[(i / 64) as usize] & (1 << (i % 64)) != 0
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the number of primes in the set.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Decides whether the set has no primes.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the primes in the set in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        let lo = self.bounds.map_or(0, |(lo, _)| lo);
+        self.bits.iter().enumerate().flat_map(move |(w, &word)| {
+            let base = lo + w as u64 * 64;
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as u64;
+                word &= word - 1;
+                Some(base + bit)
+            })
+        })
+    }
+}
+
+impl fmt::Debug for PrimeSet {
+    // The bits are left out, since there can be millions of them.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PrimeSet")
+            .field("bounds", &self.bounds)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::is_u64_prime;
+
+    #[test]
+    fn compare_prime_sets() {
+        for &(a, b) in [(0, 0), (0, 1), (0, 3), (5, 6), (0, 200_000), (999_000, 1_001_000),
+                        (1 << 41, (1 << 41) + 5000), (u64::MAX - 1000, u64::MAX)].iter() {
+            let set = PrimeSet::new(a..=b);
+            let expected: Vec<u64> = (a..=b).filter(|&n| is_u64_prime(n)).collect();
+            assert_eq!(set.iter().collect::<Vec<u64>>(), expected, "{}..={}", a, b);
+            assert_eq!(set.len(), expected.len());
+            assert_eq!(set.is_empty(), expected.is_empty());
+            for n in a.saturating_sub(100)..=b.min(a.saturating_add(10_000)) {
+                assert_eq!(set.contains(n), is_u64_prime(n) && n >= a, "{} in {}..={}", n, a, b);
+            }
+        }
+        let empty = PrimeSet::new(10..10);
+        assert!(empty.is_empty() && !empty.contains(10) && empty.iter().next().is_none());
+    }
+}
@@ -0,0 +1,178 @@
+use super::*;
+
+use super::sieve::inclusive_bounds;
+
+use rand::Rng;
+use std::ops::RangeBounds;
+
+/// Chooses a prime uniformly at random from a range.
+///
+/// Uses rejection sampling: numbers are drawn uniformly from the range and tested with
+/// `is_u64_prime()` until one is prime, so every prime in the range is equally likely.  Returns
+/// `None` if the range contains no primes.
+///
+/// Prime gaps below 2^64 are all under 1600, so a range wide enough to need many draws always
+/// has a reasonable density of primes.
+///
+/// Requires the `rand` feature.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::random_prime_in;
+/// use rand::SeedableRng;
+///
+/// fn main() {
+///     let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+///     let p = random_prime_in(1000..2000, &mut rng).unwrap();
+///     assert!(p.get() >= 1000 && p.get() < 2000);
+///     assert_eq!(random_prime_in(24..29, &mut rng), None);
+/// }
+/// ```
+pub fn random_prime_in<R, G>(range: R, rng: &mut G) -> Option<Prime>
+    where R: RangeBounds<u64>, G: Rng + ?Sized
+{
+    RandomPrimeIter::new(range, rng).next()
+}
+
+/// Chooses a prime with exactly `bits` bits uniformly at random, so that it lies in
+/// `2^(bits-1)..2^bits`.
+///
+/// Requires the `rand` feature.
+///
+/// # Panics
+///
+/// Panics if `bits` is not between 2 and 64.
+pub fn random_prime_bits<G: Rng + ?Sized>(bits: u32, rng: &mut G) -> Prime {
+    assert!((2..=64).contains(&bits), "random_prime_bits called with {} bits", bits);
+    let lo = 1_u64 << (bits - 1);
+    let hi = lo - 1 + lo;
+    random_prime_in(lo..=hi, rng).unwrap()
+}
+
+/// Produces an endless stream of primes chosen independently and uniformly at random from a
+/// range.
+///
+/// Each prime is drawn like `random_prime_in()`, by rejection sampling.  The range is checked for
+/// primes once, up front, and if it has none the iterator is empty.  The generator is owned, so
+/// pass `&mut rng` to keep using it afterwards.
+///
+/// Requires the `rand` feature.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::RandomPrimeIter;
+/// use rand::SeedableRng;
+///
+/// fn main() {
+///     let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+///     let ps: Vec<u64> = RandomPrimeIter::new(1000..2000, &mut rng).take(100).map(|p| p.get()).collect();
+///     assert!(ps.iter().all(|&p| p >= 1000 && p < 2000));
+///     assert_eq!(RandomPrimeIter::new(24..29, &mut rng).next(), None);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct RandomPrimeIter<G> {
+    /// Inclusive bounds, or None if the range has no primes.
+    bounds: Option<(u64, u64)>,
+    rng: G,
+}
+
+impl<G: Rng> RandomPrimeIter<G> {
+    /// Returns an iterator over random primes from `range`, drawn using `rng`.
+    pub fn new<R: RangeBounds<u64>>(range: R, rng: G) -> Self {
+        // make sure there is something to find
+        let bounds = inclusive_bounds(&range).filter(|&(lo, hi)| {
+            next_prime(lo.saturating_sub(1)).is_some_and(|p| p.get() >= lo && p.get() <= hi)
+        });
+        RandomPrimeIter { bounds, rng }
+    }
+}
+
+impl<G: Rng> Iterator for RandomPrimeIter<G> {
+    type Item = Prime;
+    fn next(&mut self) -> Option<Self::Item> {
+        let (lo, hi) = self.bounds?;
+        loop {
+            if let Some(p) = Prime::new(self.rng.gen_range(lo..=hi)) {
+                return Some(p);
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.bounds {
+            Some(_) => (usize::MAX, None),
+            None => (0, Some(0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn random_prime_iter() {
+        let mut rng = StdRng::seed_from_u64(7);
+        // all 25 primes below 100 should turn up, each about 4% of the time
+        let mut counts = [0_u32; 100];
+        for p in RandomPrimeIter::new(..100, &mut rng).take(25_000) {
+            counts[p.get() as usize] += 1;
+        }
+        for (n, &c) in counts.iter().enumerate() {
+            if is_u64_prime(n as u64) {
+                assert!((800..1200).contains(&c), "{} drawn {} times", n, c);
+            } else {
+                assert_eq!(c, 0);
+            }
+        }
+        assert_eq!(RandomPrimeIter::new(MAX_U64_PRIME + 1.., &mut rng).next(), None);
+        assert_eq!(RandomPrimeIter::new(0..2, &mut rng).size_hint(), (0, Some(0)));
+        let top = RandomPrimeIter::new(MAX_U64_PRIME.., &mut rng).next().map(|p| p.get());
+        assert_eq!(top, Some(MAX_U64_PRIME));
+    }
+
+    #[test]
+    fn primes_in_ranges() {
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        for _ in 0..1000 {
+            let lo = rng.gen_range(0..10_000);
+            let hi = lo + rng.gen_range(0..50);
+            let expected = (lo..hi).any(is_u64_prime);
+            match random_prime_in(lo..hi, &mut rng) {
+                Some(p) => assert!(p.get() >= lo && p.get() < hi),
+                None => assert!(!expected, "nothing found in {}..{}", lo, hi),
+            }
+        }
+        assert_eq!(random_prime_in(MAX_U64_PRIME + 1.., &mut rng), None);
+        assert_eq!(random_prime_in(0..2, &mut rng), None);
+        assert_eq!(random_prime_in(MAX_U64_PRIME.., &mut rng).map(|p| p.get()), Some(MAX_U64_PRIME));
+    }
+
+    #[test]
+    fn uniform_choice() {
+        // the six primes in 100..=130, each drawn about 1167 times
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut counts = std::collections::BTreeMap::new();
+        for _ in 0..7000 {
+            *counts.entry(random_prime_in(100..=130, &mut rng).unwrap().get()).or_insert(0) += 1;
+        }
+        assert_eq!(counts.keys().copied().collect::<Vec<u64>>(), vec![101, 103, 107, 109, 113, 127]);
+        assert!(counts.values().all(|&c| c > 1000 && c < 1400));
+    }
+
+    #[test]
+    fn bit_sizes() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for bits in 2..=64 {
+            for _ in 0..20 {
+                let p = random_prime_bits(bits, &mut rng).get();
+                assert_eq!(64 - p.leading_zeros(), bits);
+            }
+        }
+    }
+}
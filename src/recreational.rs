@@ -0,0 +1,258 @@
+use super::*;
+
+use std::convert::TryFrom;
+
+/// Returns the digits of `n` in `base`, most significant first.
+fn digits(mut n: u64, base: u64) -> Vec<u64> {
+    let mut res = Vec::new();
+    while n > 0 {
+        res.push(n % base);
+        n /= base;
+    }
+    res.reverse();
+    res
+}
+
+/// Reassembles digits into a number, or `None` if it doesn't fit in a u64.
+fn from_digits<'a, I: IntoIterator<Item = &'a u64>>(digits: I, base: u64) -> Option<u64> {
+    let mut n: u128 = 0;
+    for &d in digits {
+        n = n * base as u128 + d as u128;
+    }
+    u64::try_from(n).ok()
+}
+
+/// Decides whether `n` is a circular prime in `base`: whether every rotation of its digits is
+/// prime.
+///
+/// A rotation that moves a 0 digit to the front is read as the shorter number.  Rotations that
+/// don't fit in a u64 can't be certified, so they make the answer false.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::is_circular_prime;
+///
+/// fn main() {
+///     assert!(is_circular_prime(197, 10));  // 197, 971 and 719
+///     assert!(!is_circular_prime(23, 10));  // 32
+///     assert!(is_circular_prime(7, 2));  // 111
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `base` is less than 2.
+pub fn is_circular_prime(n: u64, base: u64) -> bool {
+    assert!(base >= 2, "is_circular_prime called with base {}", base);
+    if !is_u64_prime(n) {
+        return false;
+    }
+    let ds = digits(n, base);
+    (1..ds.len()).all(|r| {
+        let rotated = ds[r..].iter().chain(ds[..r].iter());
+        from_digits(rotated, base).is_some_and(is_u64_prime)
+    })
+}
+
+/// Decides whether `n` is an emirp in `base`: a prime whose digits reversed give a different
+/// prime.
+///
+/// Palindromic primes are excluded, since their reversal is the same prime.  A reversal that
+/// doesn't fit in a u64 can't be certified, so it makes the answer false.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::is_emirp;
+///
+/// fn main() {
+///     assert!(is_emirp(13, 10));  // 31
+///     assert!(!is_emirp(11, 10));  // palindrome
+///     assert!(!is_emirp(19, 10));  // 91 = 7 * 13
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `base` is less than 2.
+pub fn is_emirp(n: u64, base: u64) -> bool {
+    assert!(base >= 2, "is_emirp called with base {}", base);
+    if !is_u64_prime(n) {
+        return false;
+    }
+    match from_digits(digits(n, base).iter().rev(), base) {
+        Some(r) => r != n && is_u64_prime(r),
+        None => false,
+    }
+}
+
+/// Decides whether `n` is a left-truncatable prime: a prime with no 0 digits that stays prime as
+/// its leading decimal digits are removed one at a time.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::is_left_truncatable_prime;
+///
+/// fn main() {
+///     assert!(is_left_truncatable_prime(3137));  // 137, 37 and 7 are prime
+///     assert!(!is_left_truncatable_prime(3119));  // 119 = 7 * 17
+///     assert!(!is_left_truncatable_prime(103));  // contains a 0
+/// }
+/// ```
+pub fn is_left_truncatable_prime(n: u64) -> bool {
+    let mut pow: u128 = 10;
+    loop {
+        let suffix = (n as u128 % pow) as u64;
+        if (suffix as u128) < pow / 10 || !is_u64_prime(suffix) {
+            // a 0 digit, or a composite suffix
+            return false;
+        }
+        if suffix == n {
+            return true;
+        }
+        pow *= 10;
+    }
+}
+
+/// Decides whether `n` is a right-truncatable prime: a prime that stays prime as its trailing
+/// decimal digits are removed one at a time.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::is_right_truncatable_prime;
+///
+/// fn main() {
+///     assert!(is_right_truncatable_prime(7393));  // 739, 73 and 7 are prime
+///     assert!(!is_right_truncatable_prime(2111));  // 21 = 3 * 7
+/// }
+/// ```
+pub fn is_right_truncatable_prime(mut n: u64) -> bool {
+    if n == 0 {
+        return false;
+    }
+    while n > 0 {
+        if !is_u64_prime(n) {
+            return false;
+        }
+        n /= 10;
+    }
+    true
+}
+
+/// Builds every truncatable prime that fits in a u64 by adding one digit at a time to the shorter
+/// ones, then sorts them.
+fn grow_truncatable<F: Fn(u64, u64, u32) -> Option<u64>>(digits: &[u64], extend: F) -> Vec<u64> {
+    let mut all: Vec<u64> = vec![2, 3, 5, 7];
+    let mut layer = all.clone();
+    let mut len = 1;
+    while !layer.is_empty() {
+        let mut next = Vec::new();
+        for &p in layer.iter() {
+            for &d in digits {
+                if let Some(n) = extend(p, d, len).filter(|&n| is_u64_prime(n)) {
+                    next.push(n);
+                }
+            }
+        }
+        all.extend(next.iter().copied());
+        layer = next;
+        len += 1;
+    }
+    all.sort_unstable();
+    all
+}
+
+/// Returns all the left-truncatable primes that fit in a u64, in ascending order.
+///
+/// They are built by putting each nonzero digit in front of the shorter ones.  There are 4260
+/// left-truncatable primes in all, but the longest have 24 digits, and only 4242 fit.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::left_truncatable_primes;
+///
+/// fn main() {
+///     let ps = left_truncatable_primes();
+///     assert_eq!(ps[..10], [2, 3, 5, 7, 13, 17, 23, 37, 43, 47]);
+/// }
+/// ```
+pub fn left_truncatable_primes() -> Vec<u64> {
+    grow_truncatable(&[1, 2, 3, 4, 5, 6, 7, 8, 9], |p, d, len| {
+        10_u64.checked_pow(len)?.checked_mul(d)?.checked_add(p)
+    })
+}
+
+/// Returns all 83 right-truncatable primes, in ascending order.
+///
+/// They are built by appending the digits 1, 3, 7 and 9 to the shorter ones, and the largest
+/// is 73939133.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::right_truncatable_primes;
+///
+/// fn main() {
+///     let ps = right_truncatable_primes();
+///     assert_eq!(ps.len(), 83);
+///     assert_eq!(ps[..8], [2, 3, 5, 7, 23, 29, 31, 37]);
+/// }
+/// ```
+pub fn right_truncatable_primes() -> Vec<u64> {
+    grow_truncatable(&[1, 3, 7, 9], |p, d, _| p.checked_mul(10)?.checked_add(d))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reverse(n: u64) -> u64 {
+        n.to_string().chars().rev().collect::<String>().parse().unwrap()
+    }
+
+    #[test]
+    fn circular_primes() {
+        // OEIS A068652
+        let circular: Vec<u64> = (0..1_000_000).filter(|&n| is_circular_prime(n, 10)).collect();
+        assert_eq!(circular, vec![2, 3, 5, 7, 11, 13, 17, 31, 37, 71, 73, 79, 97, 113, 131, 197,
+                                  199, 311, 337, 373, 719, 733, 919, 971, 991, 1193, 1931, 3119,
+                                  3779, 7793, 7937, 9311, 9377, 11939, 19391, 19937, 37199, 39119,
+                                  71993, 91193, 93719, 93911, 99371, 193939, 199933, 319993,
+                                  331999, 391939, 393919, 919393, 933199, 939193, 939391, 993319,
+                                  999331]);
+        // the repunit R19 is prime, and all its rotations are itself
+        assert!(is_circular_prime(1_111_111_111_111_111_111, 10));
+        // Mersenne primes are circular in base 2
+        assert!(is_circular_prime((1 << 61) - 1, 2));
+        assert!(!is_circular_prime(11, 2));  // 1011 -> 0111 = 7, 1110 = 14
+    }
+
+    #[test]
+    fn truncatable_primes() {
+        let left = left_truncatable_primes();
+        let right = right_truncatable_primes();
+        for n in 0..1_000_000 {
+            assert_eq!(is_left_truncatable_prime(n), left.binary_search(&n).is_ok(), "{}", n);
+            assert_eq!(is_right_truncatable_prime(n), right.binary_search(&n).is_ok(), "{}", n);
+        }
+        assert!(left.iter().all(|&n| is_left_truncatable_prime(n)));
+        assert!(right.iter().all(|&n| is_right_truncatable_prime(n)));
+        assert_eq!(*right.last().unwrap(), 73_939_133);
+        assert_eq!(left.len(), 4242);
+    }
+
+    #[test]
+    fn emirps() {
+        for n in 0..100_000 {
+            let expected = is_u64_prime(n) && reverse(n) != n && is_u64_prime(reverse(n));
+            assert_eq!(is_emirp(n, 10), expected, "{}", n);
+        }
+        // 2^64 - 59 reversed is 75515590737044764481, which doesn't fit
+        assert!(!is_emirp(MAX_U64_PRIME, 10));
+        assert!(is_emirp(11, 2));  // 1011 -> 1101 = 13
+    }
+}
@@ -0,0 +1,136 @@
+/// Whether `r^k <= n`, without overflowing.
+fn pow_at_most(r: u64, k: u32, n: u64) -> bool {
+    match r.checked_pow(k) {
+        Some(v) => v <= n,
+        None => false,
+    }
+}
+
+/// Calculates the integer `k`-th root of `n`, the largest `r` with `r^k <= n`.
+///
+/// A floating point estimate is corrected with exact integer arithmetic, so the result is exact
+/// across the whole u64 range.
+///
+/// # Panics
+///
+/// Panics if `k` is 0.
+pub fn nth_root(n: u64, k: u32) -> u64 {
+    assert!(k > 0, "nth_root called with k = 0");
+    if k == 1 || n < 2 {
+        return n;
+    }
+    if k >= 64 {
+        return 1;
+    }
+    let mut r = (n as f64).powf(1.0 / k as f64) as u64;
+    while !pow_at_most(r, k, n) {
+        r -= 1;
+    }
+    while pow_at_most(r + 1, k, n) {
+        r += 1;
+    }
+    r
+}
+
+/// Calculates the integer square root of `n`, the largest `r` with `r^2 <= n`.
+pub fn isqrt(n: u64) -> u64 {
+    nth_root(n, 2)
+}
+
+/// Calculates the integer cube root of `n`, the largest `r` with `r^3 <= n`.
+pub fn icbrt(n: u64) -> u64 {
+    nth_root(n, 3)
+}
+
+/// Determines whether `n` is a perfect square, using `isqrt()` rather than factoring.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::is_perfect_square;
+///
+/// fn main() {
+///     assert!(is_perfect_square(4_294_967_296));
+///     assert!(!is_perfect_square(u64::MAX));
+///     assert!(is_perfect_square(0));
+/// }
+/// ```
+pub fn is_perfect_square(n: u64) -> bool {
+    // Squares are 0, 1, 4 or 9 mod 16, which rules out 3/4 of all numbers before the root.
+    if (0x0213_u16 >> (n & 15)) & 1 == 0 {
+        return false;
+    }
+    let r = isqrt(n);
+    r * r == n
+}
+
+/// Determines whether `n` is a perfect `k`-th power, using `nth_root()` rather than factoring.
+///
+/// # Panics
+///
+/// Panics if `k` is 0.
+pub fn is_kth_power(n: u64, k: u32) -> bool {
+    let r = nth_root(n, k);
+    r.checked_pow(k) == Some(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_root(n: u64, k: u32) {
+        let r = nth_root(n, k);
+        assert!(pow_at_most(r, k, n), "nth_root({}, {}) = {} is too big", n, k, r);
+        if let Some(r1) = r.checked_add(1) {
+            assert!(!pow_at_most(r1, k, n), "nth_root({}, {}) = {} is too small", n, k, r);
+        }
+    }
+
+    #[test]
+    fn small_roots() {
+        for n in 0..10_000 {
+            for k in 1..=10 {
+                check_root(n, k);
+            }
+            assert_eq!(isqrt(n), (0..=n).take_while(|r| r * r <= n).last().unwrap());
+        }
+    }
+
+    #[test]
+    fn perfect_powers() {
+        for n in 0..100_000_u64 {
+            assert_eq!(is_perfect_square(n), (0..=n).take_while(|r| r * r <= n).any(|r| r * r == n));
+        }
+        for k in 1..=64 {
+            for r in 0..2000_u64 {
+                if let Some(v) = r.checked_pow(k) {
+                    assert!(is_kth_power(v, k), "{}^{}", r, k);
+                    assert_eq!(is_kth_power(v + 1, k), k == 1 || v == 0, "{}^{} + 1", r, k);
+                }
+            }
+        }
+        assert!(is_perfect_square((u32::MAX as u64) * (u32::MAX as u64)));
+        assert!(!is_perfect_square(u64::MAX));
+        assert!(is_kth_power(1 << 63, 63) && is_kth_power(1, 64));
+    }
+
+    #[test]
+    fn boundary_roots() {
+        assert_eq!(isqrt(u64::MAX), u32::MAX as u64);
+        assert_eq!(icbrt(u64::MAX), 2_642_245);
+        assert_eq!(nth_root(u64::MAX, 63), 2);
+        assert_eq!(nth_root(u64::MAX, 64), 1);
+        for k in 1..=64 {
+            check_root(u64::MAX, k);
+            check_root(u64::MAX - 1, k);
+            // exact powers and their neighbours
+            for r in 2..2000_u64 {
+                if let Some(v) = r.checked_pow(k) {
+                    check_root(v, k);
+                    check_root(v - 1, k);
+                    check_root(v.saturating_add(1), k);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use super::*;
+
+use std::ops::{ Bound, RangeBounds };
+
+/// Converts a range of u64 into inclusive bounds, or `None` if it is empty.
+pub(crate) fn inclusive_bounds<R: RangeBounds<u64>>(range: &R) -> Option<(u64, u64)> {
+    let lo = match range.start_bound() {
+        Bound::Included(&a) => a,
+        Bound::Excluded(&a) => a.checked_add(1)?,
+        Bound::Unbounded => 0,
+    };
+    let hi = match range.end_bound() {
+        Bound::Included(&b) => b,
+        Bound::Excluded(&b) => b.checked_sub(1)?,
+        Bound::Unbounded => u64::MAX,
+    };
+    if lo <= hi {
+        Some((lo, hi))
+    } else {
+        None
+    }
+}
+
+/// Returns all primes up to and including `limit`, using a plain sieve of Eratosthenes.
+pub(crate) fn small_primes(limit: u64) -> Vec<u64> {
+    let limit = limit as usize;
+    if limit < 2 {
+        return Vec::new();
+    }
+    let mut composite = vec![false; limit + 1];
+    let mut primes = Vec::new();
+    for i in 2..=limit {
+        if composite[i] {
+            continue;
+        }
+        primes.push(i as u64);
+        for j in (i * i..=limit).step_by(i) {
+            composite[j] = true;
+        }
+    }
+    primes
+}
+
+/// Largest prime used for sieving.  Above its square, survivors are confirmed with
+/// `is_u64_prime()` instead, which is far cheaper than sieving with every prime below 2^32.
+const MAX_SIEVING_PRIME: u64 = 1 << 20;
+
+/// Number of integers handled per segment.
+pub(crate) const SEGMENT_LEN: u64 = 1 << 18;
+
+/// A segmented sieve of Eratosthenes, able to find the primes in any range below its limit.
+#[derive(Clone, Debug)]
+pub(crate) struct SegmentSieve {
+    /// Primes up to `bound`.
+    primes: Vec<u64>,
+    bound: u64,
+}
+
+impl SegmentSieve {
+    /// Prepares to sieve ranges that don't go above `limit`.
+    ///
+    /// Ranges beyond `limit` are still handled correctly, just more slowly.
+    pub(crate) fn new(limit: u64) -> Self {
+        let bound = isqrt(limit).min(MAX_SIEVING_PRIME);
+        SegmentSieve { primes: small_primes(bound), bound }
+    }
+
+    /// Appends the primes in `lo..=hi` to `out`, in ascending order.
+    ///
+    /// The whole range is sieved at once, so it should be at most a few segments wide.
+    pub(crate) fn primes_in(&self, lo: u64, hi: u64, out: &mut Vec<u64>) {
+        if lo > hi {
+            return;
+        }
+        if lo <= 2 && 2 <= hi {
+            out.push(2);
+        }
+        // Only odd numbers are sieved: index i stands for first_odd + 2i.
+        let first_odd = lo | 1;
+        if first_odd > hi {
+            return;
+        }
+        let len = ((hi - first_odd) / 2) as usize + 1;
+        let mut composite = vec![false; len];
+        for &p in self.primes.iter().skip(1) {
+            if p * p > hi {
+                break;
+            }
+            let start = first_odd.max(p * p);
+            // the first odd multiple of p at or above start
+            let first = match start % p {
+                0 => start,
+                r => match start.checked_add(p - r) {
+                    Some(f) => f,
+                    None => continue,
+                },
+            };
+            let first = if first & 1 == 0 {
+                match first.checked_add(p) {
+                    Some(f) => f,
+                    None => continue,
+                }
+            } else {
+                first
+            };
+            if first > hi {
+                continue;
+            }
+            for i in ((first - first_odd) as usize / 2..len).step_by(p as usize) {
+                composite[i] = true;
+            }
+        }
+        // Every composite up to (bound + 1)^2 - 1 has a prime factor we sieved with.
+        let exact = (self.bound as u128 + 1) * (self.bound as u128 + 1) > hi as u128;
+        for (i, &c) in composite.iter().enumerate() {
+            let n = first_odd + 2 * i as u64;
+            if !c && n >= 3 && (exact || is_u64_prime(n)) {
+                out.push(n);
+            }
+        }
+    }
+
+    /// Calls `f` with the primes in `lo..=hi`, one segment at a time in ascending order, until `f`
+    /// returns false.
+    pub(crate) fn for_segments<F>(&self, lo: u64, hi: u64, mut f: F)
+        where F: FnMut(&[u64]) -> bool
+    {
+        let mut buf = Vec::new();
+        let mut seg_lo = lo;
+        while seg_lo <= hi {
+            let seg_hi = seg_lo.saturating_add(SEGMENT_LEN - 1).min(hi);
+            buf.clear();
+            self.primes_in(seg_lo, seg_hi, &mut buf);
+            if !f(&buf) || seg_hi == hi {
+                return;
+            }
+            seg_lo = seg_hi + 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_small_primes() {
+        use primal::Primes;
+        let expected: Vec<u64> = Primes::all().map(|p| p as u64).take_while(|&p| p <= 100_000).collect();
+        assert_eq!(small_primes(100_000), expected);
+        assert_eq!(small_primes(0), vec![]);
+        assert_eq!(small_primes(2), vec![2]);
+    }
+
+    #[test]
+    fn range_bounds() {
+        assert_eq!(inclusive_bounds(&(3..7)), Some((3, 6)));
+        assert_eq!(inclusive_bounds(&(3..=7)), Some((3, 7)));
+        assert_eq!(inclusive_bounds(&(..)), Some((0, u64::MAX)));
+        assert_eq!(inclusive_bounds(&(5..5)), None);
+        assert_eq!(inclusive_bounds(&(0..0)), None);
+        assert_eq!(inclusive_bounds(&(Bound::Included(7), Bound::Included(6))), None);
+    }
+
+    fn brute_force_primes(lo: u64, hi: u64) -> Vec<u64> {
+        (lo..=hi).filter(|&n| is_u64_prime(n)).collect()
+    }
+
+    #[test]
+    fn compare_segments() {
+        let sieve = SegmentSieve::new(1 << 40);
+        for &(lo, hi) in [(0, 1000), (1, 2), (2, 2), (999_000, 1_001_000), ((1 << 40) - 5000, 1 << 40)].iter() {
+            let mut v = Vec::new();
+            sieve.primes_in(lo, hi, &mut v);
+            assert_eq!(v, brute_force_primes(lo, hi), "primes_in({}, {})", lo, hi);
+        }
+        // Past MAX_SIEVING_PRIME^2, survivors are checked individually.
+        let sieve = SegmentSieve::new(u64::MAX);
+        for &(lo, hi) in [(u64::MAX - 5000, u64::MAX), ((1 << 50) - 3000, (1 << 50) + 3000)].iter() {
+            let mut v = Vec::new();
+            sieve.primes_in(lo, hi, &mut v);
+            assert_eq!(v, brute_force_primes(lo, hi), "primes_in({}, {})", lo, hi);
+        }
+        let mut v = Vec::new();
+        sieve.for_segments(0, 3 * SEGMENT_LEN, |seg| {
+            v.extend_from_slice(seg);
+            true
+        });
+        assert_eq!(v, brute_force_primes(0, 3 * SEGMENT_LEN));
+    }
+}
@@ -0,0 +1,381 @@
+use super::*;
+
+use super::modular::{ jacobi, pow_mod };
+
+use num::{ BigUint, One, ToPrimitive, Zero };
+use std::ops::RangeBounds;
+
+/// Reduces `x` modulo the Mersenne number `m = 2^p - 1`, using `2^p ≡ 1 (mod m)`.
+fn mersenne_reduce(mut x: BigUint, p: u32, m: &BigUint) -> BigUint {
+    while x > *m {
+        x = (&x & m) + (&x >> p as usize);
+    }
+    if x == *m {
+        BigUint::zero()
+    } else {
+        x
+    }
+}
+
+/// Decides whether the Mersenne number `2^p - 1` is prime, using the Lucas-Lehmer test.
+///
+/// For odd prime `p`, `2^p - 1` is prime exactly when `s_(p-2) ≡ 0 (mod 2^p - 1)`, where
+/// `s_0 = 4` and `s_(i+1) = s_i^2 - 2`.  Composite `p` give composite `2^p - 1` and are rejected
+/// immediately, and `p = 2` is handled separately.
+///
+/// For `p <= 63` the squarings fit in u128 arithmetic.  Larger `p` use `BigUint`, reducing with
+/// shifts and masks instead of division, so the test takes `p` squarings of `p`-bit numbers.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::lucas_lehmer;
+///
+/// fn main() {
+///     assert!(lucas_lehmer(127));
+///     assert!(!lucas_lehmer(11));  // 2047 = 23 * 89
+/// }
+/// ```
+pub fn lucas_lehmer(p: u32) -> bool {
+    if p == 2 {
+        return true;
+    }
+    if !is_u64_prime(p as u64) {
+        return false;
+    }
+    if p <= 63 {
+        let m = (1_u128 << p) - 1;
+        let mut s = 4_u128;
+        for _ in 0..p - 2 {
+            s = (s * s + m - 2) % m;
+        }
+        s == 0
+    } else {
+        let m = (BigUint::one() << p as usize) - 1_u32;
+        let two = BigUint::from(2_u32);
+        let mut s = BigUint::from(4_u32);
+        for _ in 0..p - 2 {
+            s = mersenne_reduce(&s * &s + &m - &two, p, &m);
+        }
+        s.is_zero()
+    }
+}
+
+/// Produces the exponents `p` for which the Mersenne number `2^p - 1` is prime, in ascending
+/// order.
+///
+/// Each prime exponent gets one `lucas_lehmer()` test, except that when `p ≡ 3 (mod 4)` and
+/// `2p + 1` is prime, `2p + 1` divides `2^p - 1`, so those are rejected immediately.  The cost of
+/// each test grows like `p^2` or worse, so in practice this is limited to the first 20 or so
+/// exponents.  Each exponent gives the even perfect number `2^(p-1) (2^p - 1)`.
+///
+/// The iterator ends after the largest u32 prime.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::MersenneExponents;
+///
+/// fn main() {
+///     let ps: Vec<u32> = MersenneExponents::new().take(8).collect();
+///     assert_eq!(ps, vec![2, 3, 5, 7, 13, 17, 19, 31]);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct MersenneExponents {
+    primes: CertIter,
+}
+
+impl MersenneExponents {
+    /// Returns an iterator over all the Mersenne prime exponents.
+    pub fn new() -> Self {
+        Self::from(0)
+    }
+
+    /// Returns an iterator over the Mersenne prime exponents at or above `p`.
+    pub fn from(p: u32) -> Self {
+        MersenneExponents { primes: CertIter::from_pi(PrimeIter::range(p as u64, 1 << 32)) }
+    }
+}
+
+impl Default for MersenneExponents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Iterator for MersenneExponents {
+    type Item = u32;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let p = self.primes.next()?.get();
+            if p > 3 && p & 3 == 3 && is_u64_prime(2 * p + 1) {
+                continue;
+            }
+            if lucas_lehmer(p as u32) {
+                return Some(p as u32);
+            }
+        }
+    }
+}
+
+impl std::iter::FusedIterator for MersenneExponents {}
+
+/// Decides whether the Proth number `k * 2^n + 1` is prime, using Proth's theorem.
+///
+/// With `k` odd and `k < 2^n`, `N = k * 2^n + 1` is prime exactly when some `a` has
+/// `a^((N-1)/2) ≡ -1 (mod N)`.  For prime `N` every quadratic non-residue is such a witness, so
+/// rather than guessing at random, the witness is the smallest odd prime `a` with Jacobi symbol
+/// `(a/N) = -1`, which also makes the answer deterministic.  Since `N ≡ 1 (mod 4)`, reciprocity
+/// gives `(a/N) = (N mod a / a)`, which only needs `N` reduced modulo a small prime.
+///
+/// `N` is a `BigUint` internally, so `n` isn't limited by the u64 range.  The cost is dominated
+/// by one modular exponentiation of `n`-bit numbers.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::is_proth_prime;
+///
+/// fn main() {
+///     assert!(is_proth_prime(3, 2));  // 13
+///     assert!(!is_proth_prime(7, 3));  // 57 = 3 * 19
+///     assert!(is_proth_prime(1, 16));  // the Fermat prime 65537
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `k` is even or not less than `2^n`, since the theorem doesn't apply.
+pub fn is_proth_prime(k: u64, n: u32) -> bool {
+    assert!(k & 1 == 1 && (n >= 64 || k < 1 << n), "{} * 2^{} + 1 is not a Proth number", k, n);
+    if n == 1 {
+        // 3 = 1 * 2 + 1
+        return true;
+    }
+    let big_n = (BigUint::from(k) << n as usize) + 1_u32;
+    let sqrt = big_n.sqrt();
+    if &sqrt * &sqrt == big_n {
+        return false;
+    }
+    let minus_one = &big_n - 1_u32;
+    let e = &minus_one >> 1;
+    for a in CertIter::from(3) {
+        let a = a.get();
+        let r = (&big_n % a).to_u64().unwrap();
+        match jacobi(r, a) {
+            0 => return big_n == BigUint::from(a),
+            1 => continue,
+            _ => return BigUint::from(a).modpow(&e, &big_n) == minus_one,
+        }
+    }
+    unreachable!("ran out of witnesses")
+}
+
+/// Decides whether the Fermat number `F_n = 2^(2^n) + 1` is prime, using Pépin's test.
+///
+/// For `n >= 1`, `F_n` is prime exactly when `3^((F_n - 1)/2) ≡ -1 (mod F_n)`.  `F_0 = 3` is
+/// handled separately.  Up to `F_5` the arithmetic is done in u64, and beyond that with
+/// `BigUint`, where the test takes `2^n - 1` squarings of `2^n`-bit numbers.  That is quick up to
+/// about `n = 12`, and only `F_0` to `F_4` are known to be prime.
+///
+/// # Panics
+///
+/// Panics if `n >= 32`.  `F_32` has over 4 billion bits, far past what the test can handle.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::pepin_test;
+///
+/// fn main() {
+///     assert!(pepin_test(4));  // 65537
+///     assert!(!pepin_test(5));  // 4294967297 = 641 * 6700417
+/// }
+/// ```
+pub fn pepin_test(n: u32) -> bool {
+    assert!(n < 32, "pepin_test called with n = {}, F_n is too large", n);
+    if n == 0 {
+        return true;
+    }
+    if n <= 5 {
+        let f = (1_u64 << (1 << n)) + 1;
+        pow_mod(3, (f - 1) / 2, f) == f - 1
+    } else {
+        let f = (BigUint::one() << (1_usize << n)) + 1_u32;
+        let minus_one = &f - 1_u32;
+        BigUint::from(3_u32).modpow(&(&minus_one >> 1), &f) == minus_one
+    }
+}
+
+/// Decides whether `p` is a Wieferich prime: a prime with `2^(p-1) ≡ 1 (mod p^2)`.
+///
+/// Only 1093 and 3511 are known, and there are no others below 2^64.  Below 2^32 the
+/// exponentiation is done in u64 arithmetic, and above that with `BigUint`.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::is_wieferich_prime;
+///
+/// fn main() {
+///     assert!(is_wieferich_prime(1093));
+///     assert!(!is_wieferich_prime(1097));
+/// }
+/// ```
+pub fn is_wieferich_prime(p: u64) -> bool {
+    if !is_u64_prime(p) {
+        return false;
+    }
+    if p < 1 << 32 {
+        pow_mod(2, p - 1, p * p) == 1
+    } else {
+        let p = BigUint::from(p);
+        BigUint::from(2_u32).modpow(&(&p - 1_u32), &(&p * &p)).is_one()
+    }
+}
+
+/// Finds the Wieferich primes in a range, in ascending order.
+///
+/// Each prime in the range costs one modular exponentiation.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::wieferich_primes_in;
+///
+/// fn main() {
+///     assert_eq!(wieferich_primes_in(..100_000), vec![1093, 3511]);
+/// }
+/// ```
+pub fn wieferich_primes_in<R: RangeBounds<u64>>(range: R) -> Vec<u64> {
+    let mut res = Vec::new();
+    for_primes_in(range, 1024, |chunk| {
+        res.extend(chunk.iter().map(|p| p.get()).filter(|&p| is_wieferich_prime(p)));
+    });
+    res
+}
+
+/// Decides whether `p` is a Wilson prime: a prime for which `p^2` divides `(p-1)! + 1`.
+///
+/// Only 5, 13 and 563 are known.  This checks whether `wilson_quotient()` is 0, which takes O(p)
+/// time.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::is_wilson_prime;
+///
+/// fn main() {
+///     assert!(is_wilson_prime(563));
+///     assert!(!is_wilson_prime(569));
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `p` is prime and 2^32 or more, like `wilson_quotient()`.
+pub fn is_wilson_prime(p: u64) -> bool {
+    match Prime::new(p) {
+        Some(p) => wilson_quotient(p) == 0,
+        None => false,
+    }
+}
+
+/// Finds the Wilson primes in a range, in ascending order.
+///
+/// Each prime `p` in the range costs O(p) time, so the whole search is quadratic, and ranges
+/// much beyond 10^5 take a long time.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::wilson_primes_in;
+///
+/// fn main() {
+///     assert_eq!(wilson_primes_in(..1000), vec![5, 13, 563]);
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if the range contains a prime of 2^32 or more.
+pub fn wilson_primes_in<R: RangeBounds<u64>>(range: R) -> Vec<u64> {
+    let mut res = Vec::new();
+    for_primes_in(range, 1024, |chunk| {
+        res.extend(chunk.iter().filter(|&&p| wilson_quotient(p) == 0).map(|p| p.get()));
+    });
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mersenne_exponents() {
+        // OEIS A000043
+        let exponents: Vec<u32> = (0..1300).filter(|&p| lucas_lehmer(p)).collect();
+        assert_eq!(exponents, vec![2, 3, 5, 7, 13, 17, 19, 31, 61, 89, 107, 127, 521, 607, 1279]);
+    }
+
+    #[test]
+    fn mersenne_iter() {
+        let ps: Vec<u32> = MersenneExponents::new().take_while(|&p| p < 700).collect();
+        assert_eq!(ps, vec![2, 3, 5, 7, 13, 17, 19, 31, 61, 89, 107, 127, 521, 607]);
+        assert_eq!(MersenneExponents::from(62).next(), Some(89));
+        assert_eq!(MersenneExponents::from(u32::MAX).next(), None);
+        // the rejected exponents are never Mersenne prime exponents
+        for p in PrimeIter::range(4, 2000).filter(|p| p & 3 == 3 && is_u64_prime(2 * p + 1)) {
+            assert!(!lucas_lehmer(p as u32), "{}", p);
+        }
+    }
+
+    #[test]
+    fn compare_proth() {
+        for n in 1..40 {
+            for k in (1..(1_u64 << n).min(2000)).step_by(2) {
+                let big_n = (k << n) + 1;
+                assert_eq!(is_proth_prime(k, n), is_u64_prime(big_n), "{} * 2^{} + 1", k, n);
+            }
+        }
+        // 3 * 2^n + 1 is prime for these n, OEIS A002253
+        let threes: Vec<u32> = (2..600).filter(|&n| is_proth_prime(3, n)).collect();
+        assert_eq!(threes, vec![2, 5, 6, 8, 12, 18, 30, 36, 41, 66, 189, 201, 209, 276, 353, 408, 438, 534]);
+        // 78557 is a Sierpinski number, with covering set {3, 5, 7, 13, 19, 37, 73}
+        assert!((17..200).all(|n| !is_proth_prime(78557, n)));
+    }
+
+    #[test]
+    fn wieferich_and_wilson() {
+        for p in PrimeIter::range(0, 5000) {
+            let two = (0..p - 1).fold(1_u128, |acc, _| acc * 2 % (p as u128 * p as u128));
+            assert_eq!(is_wieferich_prime(p), two == 1, "{}", p);
+        }
+        assert_eq!(wieferich_primes_in(..1_000_000), vec![1093, 3511]);
+        assert_eq!(wieferich_primes_in(u64::MAX - 10_000..), Vec::<u64>::new());
+        assert!(!is_wieferich_prime(1093 * 3511));
+        assert_eq!(wilson_primes_in(..2000), vec![5, 13, 563]);
+        assert_eq!(wilson_primes_in(6..13), Vec::<u64>::new());
+        assert!(!is_wilson_prime(25));
+    }
+
+    #[test]
+    fn fermat_numbers() {
+        let primes: Vec<u32> = (0..=11).filter(|&n| pepin_test(n)).collect();
+        assert_eq!(primes, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "F_n is too large")]
+    fn pepin_bound() {
+        pepin_test(64);
+    }
+
+    #[test]
+    fn compare_small_mersennes() {
+        for p in 0..64 {
+            assert_eq!(lucas_lehmer(p), is_u64_prime((1 << p) - 1), "2^{} - 1", p);
+        }
+    }
+}
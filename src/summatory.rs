@@ -0,0 +1,366 @@
+use super::*;
+use super::sieve::small_primes;
+
+/// Sieves the Möbius function over `0..=limit`, with μ(0) = 0.
+pub(crate) fn mobius_sieve(limit: usize) -> Vec<i8> {
+    let mut mu = vec![1_i8; limit + 1];
+    mu[0] = 0;
+    let mut composite = vec![false; limit + 1];
+    let mut primes = Vec::new();
+    for i in 2..=limit {
+        if !composite[i] {
+            primes.push(i);
+            mu[i] = -1;
+        }
+        for &p in primes.iter() {
+            if i * p > limit {
+                break;
+            }
+            composite[i * p] = true;
+            if i.is_multiple_of(p) {
+                mu[i * p] = 0;
+                break;
+            }
+            mu[i * p] = -mu[i];
+        }
+    }
+    mu
+}
+
+/// Chooses how far to sieve: about x^(2/3), which balances the two halves of the algorithm.  It
+/// is never below sqrt(x), so that `hyperbola_sum()` only meets quotients above it one at a time.
+fn small_limit(x: u64) -> u64 {
+    let c = icbrt(x);
+    (c * c).max(isqrt(x)).max(1024).min(x)
+}
+
+/// Computes S(x) = f(1) + ... + f(x) for an arithmetic function f whose Dirichlet convolution
+/// with the constant function 1 has the known summatory function `g`, using the identity
+/// `S(x/1) + S(x/2) + ... + S(x/x) = g(x)`.
+///
+/// `values(lo, out)` must fill `out[i]` with f(lo + i).  It is called on consecutive segments of
+/// `seg_len` numbers up to `limit`, and the running sum gives S(q) for every `q <= limit` in
+/// turn.  Each of the values S(x/k) above `limit` needs the S(x/(kd)) for `d >= 2`.  The ones up
+/// to `limit` are added to `small[k]` as their segment goes by: the quotients below sqrt(x/k)
+/// with the number of `d` giving each, and the larger ones one `d` at a time.  Then the S(x/k)
+/// are finished from largest `k` to smallest, so only the segment and two entries per `k` are
+/// ever held in memory.
+fn hyperbola_sum<F, G>(x: u64, limit: u64, seg_len: u64, mut values: F, g: G) -> i128
+    where F: FnMut(u64, &mut [i128]), G: Fn(u64) -> i128
+{
+    const DONE: u64 = u64::MAX;
+    let kmax = (x / (limit + 1)) as usize;
+    // next[k] is the next quotient q <= limit still to be added to small[k]
+    let mut next = vec![1_u64; kmax + 1];
+    let mut small = vec![0_i128; kmax + 1];
+    let mut seg = vec![0_i128; seg_len.min(limit) as usize];
+    let mut sum: i128 = 0;
+    let mut lo = 1;
+    while lo <= limit {
+        let hi = (lo + seg_len - 1).min(limit);
+        let seg = &mut seg[..(hi - lo + 1) as usize];
+        values(lo, seg);
+        for s in seg.iter_mut() {
+            sum += *s;
+            *s = sum;
+        }
+        let at = |q: u64| seg[(q - lo) as usize];
+        for k in 1..=kmax {
+            let mut q = next[k];
+            if q > hi {
+                continue;
+            }
+            let v = x / k as u64;
+            let r = isqrt(v);
+            let dense = v / (r + 1);
+            let mut acc: i128 = 0;
+            // the quotients up to v / (r + 1) come from the d in (max(v / (q + 1), r), v / q]
+            let mut d_hi = v / q;
+            while q <= dense && q <= hi {
+                let d_lo = v / (q + 1);
+                acc += (d_hi - d_lo.max(r)) as i128 * at(q);
+                d_hi = d_lo;
+                q += 1;
+            }
+            if q == dense + 1 {
+                q = v / r;
+            }
+            // the quotients v / d for d <= r are all distinct
+            if q > dense && q <= hi {
+                let mut d = v / q;
+                loop {
+                    acc += at(q);
+                    if d == 2 {
+                        q = DONE;
+                        break;
+                    }
+                    d -= 1;
+                    q = v / d;
+                    if q > hi {
+                        break;
+                    }
+                }
+            }
+            if q > limit {
+                q = DONE;
+            }
+            small[k] += acc;
+            next[k] = q;
+        }
+        lo = hi + 1;
+    }
+    if kmax == 0 {
+        return sum;
+    }
+    // S(x / k) for k <= kmax, reusing small, from the S(x / (kd)) with kd <= kmax
+    for k in (1..=kmax).rev() {
+        let mut total = g(x / k as u64) - small[k];
+        for kd in (2 * k..=kmax).step_by(k) {
+            total -= small[kd];
+        }
+        small[k] = total;
+    }
+    small[1]
+}
+
+/// Chooses the segment length for `hyperbola_sum()`, about x^(1/3), so that the segment is no
+/// bigger than the per-quotient tables.
+fn segment_len(limit: u64) -> u64 {
+    isqrt(limit).max(1 << 12)
+}
+
+/// Sieves μ(n) for `n` in `lo..lo + out.len()`, using `primes`, which must include every prime up
+/// to the square root of the last `n`.  `prod` is scratch space.
+fn mobius_segment(lo: u64, out: &mut [i128], prod: &mut Vec<i64>, primes: &[u64]) {
+    let len = out.len() as u64;
+    let hi = lo + len - 1;
+    // prod[i] becomes ± the product of the small primes dividing lo + i, negated once for each
+    // of them, or 0 if a square divides it
+    prod.clear();
+    prod.resize(len as usize, 1);
+    for &p in primes {
+        if p * p > hi {
+            break;
+        }
+        for i in (lo.div_ceil(p) * p - lo..len).step_by(p as usize) {
+            prod[i as usize] *= -(p as i64);
+        }
+        let pp = p * p;
+        for i in (lo.div_ceil(pp) * pp - lo..len).step_by(pp as usize) {
+            prod[i as usize] = 0;
+        }
+    }
+    // a squarefree n with n != |prod| has exactly one more prime factor, above sqrt(n)
+    for (i, (m, &pr)) in out.iter_mut().zip(prod.iter()).enumerate() {
+        *m = if pr.unsigned_abs() == lo + i as u64 { pr.signum() } else { -pr.signum() } as i128;
+    }
+}
+
+/// Mertens function
+///
+/// Calculates M(x) = μ(1) + μ(2) + ... + μ(x) in roughly O(x^(2/3)) time and O(x^(1/3)) memory.
+/// The Möbius function is sieved in segments up to about x^(2/3), and the remaining values M(x/k)
+/// come from the identity `M(x/1) + M(x/2) + ... + M(x/x) = 1`.  Small `x` are answered from the
+/// sieve alone.
+///
+/// This takes about 3 seconds for 10^12, and the time grows by a factor of about 5 per decade, so
+/// 10^14 takes about a minute and values near 2^64 around two days.  The memory is a few
+/// megabytes up to 10^14, growing to about 150 MB near 2^64.
+pub fn mertens(x: u64) -> i64 {
+    let limit = small_limit(x);
+    let primes = small_primes(isqrt(limit));
+    let mut prod = Vec::new();
+    let values = |lo, out: &mut [i128]| mobius_segment(lo, out, &mut prod, &primes);
+    hyperbola_sum(x, limit, segment_len(limit), values, |_| 1) as i64
+}
+
+/// Sieves Euler's totient function for `n` in `lo..lo + out.len()`, using `primes`, which must
+/// include every prime up to the square root of the last `n`.  `phi` and `prod` are scratch
+/// space.
+fn totient_segment(lo: u64, out: &mut [i128], phi: &mut Vec<u64>, prod: &mut Vec<u64>, primes: &[u64]) {
+    let len = out.len() as u64;
+    let hi = lo + len - 1;
+    phi.clear();
+    phi.resize(len as usize, 1);
+    prod.clear();
+    prod.resize(len as usize, 1);
+    for &p in primes {
+        if p * p > hi {
+            break;
+        }
+        for i in (lo.div_ceil(p) * p - lo..len).step_by(p as usize) {
+            phi[i as usize] *= p - 1;
+            prod[i as usize] *= p;
+        }
+        // each further power of p dividing n contributes another factor of p
+        let mut q = p * p;
+        while q <= hi {
+            for i in (lo.div_ceil(q) * q - lo..len).step_by(q as usize) {
+                phi[i as usize] *= p;
+                prod[i as usize] *= p;
+            }
+            q = match q.checked_mul(p) {
+                Some(q) => q,
+                None => break,
+            };
+        }
+    }
+    // what is left of n after the small primes is 1 or a prime above sqrt(n)
+    for (i, (t, (&ph, &pr))) in out.iter_mut().zip(phi.iter().zip(prod.iter())).enumerate() {
+        let rest = (lo + i as u64) / pr;
+        *t = if rest > 1 { ph * (rest - 1) } else { ph } as i128;
+    }
+}
+
+/// Totient summatory function
+///
+/// Calculates Φ(x) = φ(1) + φ(2) + ... + φ(x) in roughly O(x^(2/3)) time and O(x^(1/3)) memory,
+/// with the same approach as `mertens()`, using the identity
+/// `Φ(x/1) + Φ(x/2) + ... + Φ(x/x) = x(x+1)/2`.
+///
+/// Φ(x) is about 0.3x^2, so the result is a u128.  It counts the coprime pairs `(a, b)` with
+/// `1 <= a <= b <= x`, and is one less than the length of the Farey sequence of order `x`.
+///
+/// The running time and memory are close to those of `mertens()`.
+pub fn totient_sum(x: u64) -> u128 {
+    let limit = small_limit(x);
+    let primes = small_primes(isqrt(limit));
+    let (mut phi, mut prod) = (Vec::new(), Vec::new());
+    let values = |lo, out: &mut [i128]| totient_segment(lo, out, &mut phi, &mut prod, &primes);
+    // v(v+1)/2, halving first so that it can't overflow
+    let triangle = |v: u64| {
+        let (a, b) = (v as i128, v as i128 + 1);
+        if v & 1 == 0 { a / 2 * b } else { a * (b / 2) }
+    };
+    hyperbola_sum(x, limit, segment_len(limit), values, triangle) as u128
+}
+
+/// Counts the `k`-free integers in `1..=x`, those not divisible by any `k`-th power other than 1.
+///
+/// Uses the identity `Q_k(x) = Σ μ(d) floor(x / d^k)` over `d <= x^(1/k)`, with the Möbius
+/// function sieved up to `x^(1/k)`.  This takes O(x^(1/k)) time and memory, so squarefree counts
+/// are practical up to around 10^16.
+///
+/// # Panics
+///
+/// Panics if `k` is less than 2.
+pub fn count_k_free(x: u64, k: u32) -> u64 {
+    assert!(k >= 2, "count_k_free called with k = {}", k);
+    let limit = nth_root(x, k);
+    let mu = mobius_sieve(limit as usize);
+    let mut res: i128 = 0;
+    for d in 1..=limit {
+        let m = mu[d as usize];
+        if m != 0 {
+            res += m as i128 * (x / d.pow(k)) as i128;
+        }
+    }
+    res as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_mertens() {
+        let mut m = 0;
+        for x in 1..5000 {
+            m += mobius(x, 1);
+            assert_eq!(mertens(x), m, "mertens({})", x);
+        }
+        assert_eq!(mertens(0), 0);
+    }
+
+    #[test]
+    fn big_mertens() {
+        // OEIS A084237
+        assert_eq!(mertens(1_000_000), 212);
+        assert_eq!(mertens(10_000_000), 1037);
+        assert_eq!(mertens(100_000_000), 1928);
+        assert_eq!(mertens(1_000_000_000), -222);
+        assert_eq!(mertens(100_000_000_000), -87856);
+    }
+
+    #[test]
+    fn mid_summatory() {
+        // far enough out that hyperbola_sum has several segments and quotient tables
+        let limit = 3_000_000;
+        let mu = mobius_sieve(limit);
+        let mut phi: Vec<u64> = (0..=limit as u64).collect();
+        for p in 2..=limit {
+            if phi[p] == p as u64 {
+                for j in (p..=limit).step_by(p) {
+                    phi[j] -= phi[j] / p as u64;
+                }
+            }
+        }
+        let (mut m, mut t) = (0, 0);
+        let mut next = 5000;
+        for x in 1..=limit as u64 {
+            m += mu[x as usize] as i64;
+            t += phi[x as usize] as u128;
+            if x == next {
+                assert_eq!(mertens(x), m, "mertens({})", x);
+                assert_eq!(totient_sum(x), t, "totient_sum({})", x);
+                next += next / 7 + 1;
+            }
+        }
+    }
+
+    #[test]
+    fn small_totient_sums() {
+        let mut t = 0;
+        for x in 1..5000 {
+            t += euler_totient(x) as u128;
+            assert_eq!(totient_sum(x), t, "totient_sum({})", x);
+        }
+        assert_eq!(totient_sum(0), 0);
+    }
+
+    #[test]
+    fn big_totient_sums() {
+        // OEIS A064018
+        assert_eq!(totient_sum(1_000_000), 303_963_552_392);
+        assert_eq!(totient_sum(1_000_000_000), 303_963_551_173_008_414);
+        assert_eq!(totient_sum(100_000_000_000), 3_039_635_509_283_386_211_140);
+    }
+
+    #[test]
+    fn k_free_counts() {
+        for k in 2..=4 {
+            let mut count = 0;
+            for x in 1..3000 {
+                if is_k_free(x, k) {
+                    count += 1;
+                }
+                assert_eq!(count_k_free(x, k), count, "count_k_free({}, {})", x, k);
+            }
+        }
+        assert_eq!(count_k_free(0, 2), 0);
+        // OEIS A071172
+        assert_eq!(count_k_free(1_000_000_000_000, 2), 607_927_102_274);
+        assert_eq!(count_k_free(u64::MAX, 64), u64::MAX);
+    }
+
+    #[test]
+    fn compare_sieves() {
+        let mu = mobius_sieve(10_000);
+        for n in 1..=10_000 {
+            assert_eq!(mu[n as usize] as i64, mobius(n, 1));
+        }
+        let primes = small_primes(1 << 16);
+        let (mut prod, mut phi, mut scratch) = (Vec::new(), Vec::new(), Vec::new());
+        let mut out = vec![0; 1000];
+        for lo in [1, 2, 9_000, 1_000_000_000, (1 << 32) - 500] {
+            mobius_segment(lo, &mut out, &mut scratch, &primes);
+            for (i, &m) in out.iter().enumerate() {
+                assert_eq!(m as i64, mobius(lo + i as u64, 1), "mobius_segment at {}", lo + i as u64);
+            }
+            totient_segment(lo, &mut out, &mut phi, &mut prod, &primes);
+            for (i, &t) in out.iter().enumerate() {
+                assert_eq!(t as u64, euler_totient(lo + i as u64), "totient_segment at {}", lo + i as u64);
+            }
+        }
+    }
+}
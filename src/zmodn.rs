@@ -0,0 +1,191 @@
+use super::modular::{ inv_mod, mul_mod };
+
+/// Arithmetic in the ring of integers modulo a fixed `n`.
+///
+/// The constants needed for fast reduction are computed once in `ZmodN::new()`.  Odd moduli use
+/// Montgomery multiplication, which avoids 128-bit division entirely; even moduli fall back to
+/// u128 remainders.
+///
+/// Ring elements are `ZmodNElem` values in an internal representation.  Create them with
+/// `elem()` and read them back with `value()`.  Elements from one `ZmodN` must not be used with
+/// another.
+///
+/// # Example
+///
+/// ```
+/// use red_primality::ZmodN;
+///
+/// fn main() {
+///     let zn = ZmodN::new(101);
+///     let x = zn.elem(7);
+///     let y = zn.mul(zn.pow(x, 100), zn.inv(x).unwrap());
+///     assert_eq!(zn.value(zn.mul(y, x)), 1);
+///     assert_eq!(zn.value(zn.sub(zn.zero(), zn.one())), 100);
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ZmodN {
+    n: u64,
+    /// -n^-1 mod 2^64, for Montgomery reduction.
+    ninv: u64,
+    /// 2^128 mod n, for converting into Montgomery form.
+    r2: u64,
+    /// The representation of 1.
+    one: u64,
+    /// Whether elements are held in Montgomery form.
+    mont: bool,
+}
+
+/// An element of the ring managed by a `ZmodN`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ZmodNElem(u64);
+
+impl ZmodN {
+    /// Precomputes the reduction constants for modulus `n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn new(n: u64) -> Self {
+        assert!(n > 0, "ZmodN::new called with modulus 0");
+        if n & 1 == 1 {
+            // Newton's iteration doubles the number of correct low bits each step.
+            let mut inv = n;
+            for _ in 0..5 {
+                inv = inv.wrapping_mul(2_u64.wrapping_sub(n.wrapping_mul(inv)));
+            }
+            let r1 = (u64::MAX % n + 1) % n;
+            let r2 = mul_mod(r1, r1, n);
+            ZmodN { n, ninv: inv.wrapping_neg(), r2, one: r1, mont: true }
+        } else {
+            ZmodN { n, ninv: 0, r2: 0, one: 1 % n, mont: false }
+        }
+    }
+    /// The modulus.
+    pub fn modulus(&self) -> u64 {
+        self.n
+    }
+    /// Montgomery reduction: computes t / 2^64 mod n, for t < n * 2^64.
+    fn redc(&self, t: u128) -> u64 {
+        let m = (t as u64).wrapping_mul(self.ninv);
+        let mn = m as u128 * self.n as u128;
+        let (_, carry) = (t as u64).overflowing_add(mn as u64);
+        let res = (t >> 64) + (mn >> 64) + carry as u128;
+        if res >= self.n as u128 {
+            (res - self.n as u128) as u64
+        } else {
+            res as u64
+        }
+    }
+    /// Converts `x` to an element, reducing it mod n.
+    pub fn elem(&self, x: u64) -> ZmodNElem {
+        let x = x % self.n;
+        if self.mont {
+            ZmodNElem(self.redc(x as u128 * self.r2 as u128))
+        } else {
+            ZmodNElem(x)
+        }
+    }
+    /// Returns the canonical value of `a`, in the range `0..n`.
+    pub fn value(&self, a: ZmodNElem) -> u64 {
+        if self.mont {
+            self.redc(a.0 as u128)
+        } else {
+            a.0
+        }
+    }
+    /// The additive identity.
+    pub fn zero(&self) -> ZmodNElem {
+        ZmodNElem(0)
+    }
+    /// The multiplicative identity.
+    pub fn one(&self) -> ZmodNElem {
+        ZmodNElem(self.one)
+    }
+    /// Computes `a + b`.
+    pub fn add(&self, a: ZmodNElem, b: ZmodNElem) -> ZmodNElem {
+        let (s, carry) = a.0.overflowing_add(b.0);
+        if carry || s >= self.n {
+            ZmodNElem(s.wrapping_sub(self.n))
+        } else {
+            ZmodNElem(s)
+        }
+    }
+    /// Computes `a - b`.
+    pub fn sub(&self, a: ZmodNElem, b: ZmodNElem) -> ZmodNElem {
+        if a.0 >= b.0 {
+            ZmodNElem(a.0 - b.0)
+        } else {
+            ZmodNElem(a.0.wrapping_sub(b.0).wrapping_add(self.n))
+        }
+    }
+    /// Computes `-a`.
+    pub fn neg(&self, a: ZmodNElem) -> ZmodNElem {
+        self.sub(self.zero(), a)
+    }
+    /// Computes `a * b`.
+    pub fn mul(&self, a: ZmodNElem, b: ZmodNElem) -> ZmodNElem {
+        if self.mont {
+            ZmodNElem(self.redc(a.0 as u128 * b.0 as u128))
+        } else {
+            ZmodNElem(mul_mod(a.0, b.0, self.n))
+        }
+    }
+    /// Computes `a^p`.
+    pub fn pow(&self, mut a: ZmodNElem, mut p: u64) -> ZmodNElem {
+        let mut res = self.one();
+        while p > 0 {
+            if p & 1 == 1 {
+                res = self.mul(res, a);
+            }
+            a = self.mul(a, a);
+            p >>= 1;
+        }
+        res
+    }
+    /// Computes the multiplicative inverse of `a`, if it is a unit.
+    pub fn inv(&self, a: ZmodNElem) -> Option<ZmodNElem> {
+        inv_mod(self.value(a), self.n).map(|x| self.elem(x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modular::pow_mod;
+
+    fn test_modulus(n: u64) {
+        let zn = ZmodN::new(n);
+        let samples = [0, 1, 2, 3, n / 2, n / 3 + 1, n.wrapping_sub(1), n.wrapping_sub(2), u64::MAX];
+        for &x in samples.iter() {
+            let a = zn.elem(x);
+            assert_eq!(zn.value(a), x % n, "n={}, x={}", n, x);
+            assert_eq!(zn.value(zn.neg(a)), (n - x % n) % n, "n={}, x={}", n, x);
+            assert_eq!(zn.inv(a).map(|i| zn.value(i)), inv_mod(x % n, n), "n={}, x={}", n, x);
+            for &y in samples.iter() {
+                let b = zn.elem(y);
+                let (xm, ym) = ((x % n) as u128, (y % n) as u128);
+                let n128 = n as u128;
+                assert_eq!(zn.value(zn.add(a, b)) as u128, (xm + ym) % n128, "n={}, {}+{}", n, x, y);
+                assert_eq!(zn.value(zn.sub(a, b)) as u128, (xm + n128 - ym) % n128, "n={}, {}-{}", n, x, y);
+                assert_eq!(zn.value(zn.mul(a, b)), mul_mod(x, y, n), "n={}, {}*{}", n, x, y);
+                assert_eq!(zn.value(zn.pow(a, y)), pow_mod(x, y, n), "n={}, {}^{}", n, x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn compare_arithmetic() {
+        for n in 1..300 {
+            test_modulus(n);
+        }
+        for n in (u64::MAX - 300)..=u64::MAX {
+            test_modulus(n);
+        }
+        for mag in 31..64 {
+            test_modulus(1 << mag);
+            test_modulus((1 << mag) + 1);
+            test_modulus((1 << mag) - 1);
+        }
+    }
+}